@@ -9,6 +9,7 @@ extern crate web_sys;
 
 use squark::{uuid, App, Child, HandlerArg, Runtime, View, Task};
 use squark_macros::view;
+use squark_web::task::focus;
 use squark_web::WebRuntime;
 use std::iter::FromIterator;
 use wasm_bindgen::prelude::*;
@@ -80,7 +81,7 @@ impl Entry {
         }
 
         view! {
-            <li key={ self.id.clone() } class={ class.join(" ") }>
+            <li key={ self.id.clone() } class={ class }>
                 {
                     if editing {
                         let id = format!("edit-{}", i);
@@ -89,16 +90,15 @@ impl Entry {
                                 id={ id.clone() }
                                 class="edit"
                                 type="text"
-                                value={ self.description.clone() }
+                                prop:value={ self.description.clone() }
                                 oninput={ |v| match v {
                                     HandlerArg::String(v) => Some(Action::UpdateEntry(v)),
                                     _ => None,
                                 } }
-                                onkeydown={ |v| match v {
-                                    HandlerArg::String(ref v) if v.as_str() == "Enter" => {
-                                        Some(Action::EndEditing)
-                                    }
-                                    _ => None,
+                                onkeydown={ |v| if is_enter(&v) {
+                                    Some(Action::EndEditing)
+                                } else {
+                                    None
                                 } }
                                 onblur={ move |_| Some(Action::EndEditing) } />
                         }
@@ -108,7 +108,7 @@ impl Entry {
                                 <input
                                     class="toggle"
                                     type="checkbox"
-                                    checked={ completed }
+                                    prop:checked={ completed }
                                     onclick={
                                         move |_| {
                                             Some(Action::Check(i, !completed))
@@ -179,6 +179,11 @@ enum Action {
     ChangeVisibility(Visibility),
 }
 
+/// Enter which is not to commit IME composition
+fn is_enter(v: &HandlerArg) -> bool {
+    v["key"] == "Enter" && v["isComposing"] == false
+}
+
 fn header_view(state: &State) -> View<Action> {
     view! {
         <header class="header">
@@ -186,15 +191,12 @@ fn header_view(state: &State) -> View<Action> {
             <input
                 class="new-todo"
                 placeholder="What needs to be done?"
-                value={ state.field.clone() }
+                prop:value={ state.field.clone() }
                 oninput={ |v| match v {
                     HandlerArg::String(v) => Some(Action::UpdateField(v)),
                     _ => None,
                 } }
-                onkeydown={ |v| match v {
-                    HandlerArg::String(ref v) if v.as_str() == "Enter" => Some(Action::Add),
-                    _ => None,
-                } } />
+                onkeydown={ |v| if is_enter(&v) { Some(Action::Add) } else { None } } />
         </header>
     }
 }
@@ -210,7 +212,7 @@ fn main_view(state: &State) -> View<Action> {
                             <input
                                 class="toggle-all"
                                 type="checkbox"
-                                checked={ is_all_completed }
+                                prop:checked={ is_all_completed }
                                 onclick={
                                     move |_| {
                                         Some(Action::CheckAll(!is_all_completed))
@@ -324,6 +326,7 @@ impl App for TodoApp {
             }
             Action::EditEntry(i) => {
                 state.editing = Some(i);
+                return (state, focus(format!("#edit-{}", i)));
             }
             Action::ChangeVisibility(v) => {
                 state.visibility = v;
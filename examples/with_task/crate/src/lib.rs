@@ -6,26 +6,30 @@ extern crate wasm_bindgen;
 extern crate wasm_bindgen_futures;
 extern crate web_sys;
 extern crate js_sys;
-extern crate futures;
 
-use squark::{App, Runtime, View, Task};
+use squark::{App, Runtime, View, Task, Sub};
 use squark_macros::view;
-use squark_web::WebRuntime;
+use squark_web::{sub, WebRuntime};
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
 use wasm_bindgen_futures::JsFuture;
 use web_sys::window;
 use js_sys::Promise;
-use futures::prelude::*;
 
 #[derive(Clone, Debug, PartialEq)]
 struct State {
     count: isize,
+    ticking: bool,
+    error: Option<String>,
 }
 
 impl State {
     pub fn new() -> State {
-        State { count: 0 }
+        State {
+            count: 0,
+            ticking: false,
+            error: None,
+        }
     }
 }
 
@@ -34,6 +38,8 @@ enum Action {
     Increment,
     Decrement,
     Timeout,
+    ToggleTicking,
+    Failed(String),
 }
 
 #[derive(Clone, Debug)]
@@ -59,12 +65,17 @@ impl App for CounterApp {
                     window().unwrap().set_timeout_with_callback_and_timeout_and_arguments_0(closure.as_ref().unchecked_ref(), 1000).unwrap();
                     closure.forget();
                 });
-                let future = JsFuture::from(p)
-                    .map(move |_| {
-                        Action::Increment
-                    })
-                    .map_err(|e| panic!("delay errored; err={:?}", e));
-                task.push(Box::new(future));
+                task = Task::perform(
+                    JsFuture::from(p),
+                    |_| Action::Increment,
+                    |e| Action::Failed(format!("{:?}", e)),
+                );
+            }
+            Action::ToggleTicking => {
+                state.ticking = !state.ticking;
+            }
+            Action::Failed(e) => {
+                state.error = Some(e);
             }
         };
         (state, task)
@@ -72,9 +83,11 @@ impl App for CounterApp {
 
     fn view(&self, state: State) -> View<Action> {
         let count = state.count;
+        let ticking = state.ticking;
         view! {
             <div>
                 { count.to_string() }
+                { state.error.clone() }
                 <button onclick={ move |_| Some(Action::Increment) }>
                     increment
                 </button>
@@ -84,9 +97,19 @@ impl App for CounterApp {
                 <button onclick={ move |_| Some(Action::Timeout) }>
                     timeout
                 </button>
+                <button onclick={ move |_| Some(Action::ToggleTicking) }>
+                    { if ticking { "stop ticking" } else { "start ticking" } }
+                </button>
             </div>
         }
     }
+
+    fn subscriptions(&self, state: &State) -> Sub<Action> {
+        if !state.ticking {
+            return Sub::empty();
+        }
+        sub::interval(1000, |_| Some(Action::Increment))
+    }
 }
 
 impl Default for CounterApp {
@@ -19,6 +19,9 @@ mod parser {
     pub struct Parser;
 }
 
+/// Options which can follow handler name, such as `onsubmit|prevent`
+const LISTENER_OPTIONS: &[&str] = &["prevent", "stop", "capture", "passive", "once"];
+
 fn get_token_stream(mut tag_pairs: Pairs<Rule>) -> TokenStream {
     let name = tag_pairs.next().expect("name").as_str();
     let _name = TokenTree::Literal(Literal::string(name));
@@ -50,6 +53,15 @@ fn get_token_stream(mut tag_pairs: Pairs<Rule>) -> TokenStream {
 
         if k.starts_with("on") {
             let (_, k) = k.split_at(2);
+            for option in k.split('|').skip(1) {
+                if !LISTENER_OPTIONS.contains(&option) {
+                    panic!("unknown listener option `{}` of `on{}`", option, k);
+                }
+            }
+            let options: Vec<&str> = k.split('|').skip(1).collect();
+            if options.contains(&"passive") && options.contains(&"prevent") {
+                panic!("`prevent` of `on{}` is ignored by browser since listener is `passive`", k);
+            }
             let _k = TokenTree::Literal(Literal::string(k));
             handlers.push(quote! {
                 ($_k.to_string(), _squark::handler($_v)),
@@ -57,6 +69,9 @@ fn get_token_stream(mut tag_pairs: Pairs<Rule>) -> TokenStream {
             continue;
         }
 
+        if k.contains('|') {
+            panic!("listener options are given to attribute `{}`", k);
+        }
         let _k = TokenTree::Literal(Literal::string(k));
         attributes.push(quote! {
             ($_k.to_string(), $_v),
@@ -65,34 +80,10 @@ fn get_token_stream(mut tag_pairs: Pairs<Rule>) -> TokenStream {
     let _attributes = TokenStream::from_iter(attributes);
     let _handlers = TokenStream::from_iter(handlers);
 
-    let mut children = vec![];
-    if let Some(children_pair) = tag_pairs.next() {
-        for p in children_pair.into_inner() {
-            let token = match p.as_rule() {
-                Rule::tag => {
-                    let _tag = get_token_stream(p.into_inner());
-                    quote! {
-                        _squark::Child::from($_tag),
-                    }
-                }
-                Rule::text => {
-                    let _text = TokenTree::Literal(Literal::string(p.as_str()));
-                    quote! {
-                        $_text.into(),
-                    }
-                }
-                Rule::embedded => {
-                    let _embedded = TokenStream::from_str(p.as_str()).unwrap();
-                    quote! {
-                        {$_embedded}.into(),
-                    }
-                }
-                _ => unreachable!(),
-            };
-            children.push(token);
-        }
-    }
-    let _children = TokenStream::from_iter(children);
+    let _children = match tag_pairs.next() {
+        Some(children_pair) => get_children_token_stream(children_pair),
+        None => TokenStream::new(),
+    };
 
     quote! {
         _squark::View::new(
@@ -110,11 +101,60 @@ fn get_token_stream(mut tag_pairs: Pairs<Rule>) -> TokenStream {
     }
 }
 
+fn get_fragment_token_stream(mut fragment_pairs: Pairs<Rule>) -> TokenStream {
+    let _children = get_children_token_stream(fragment_pairs.next().expect("children"));
+
+    quote! {
+        _squark::View::fragment(
+            vec![
+                $_children
+            ]
+        )
+    }
+}
+
+fn get_node_token_stream(pair: Pair<Rule>) -> TokenStream {
+    match pair.as_rule() {
+        Rule::tag => get_token_stream(pair.into_inner()),
+        Rule::fragment => get_fragment_token_stream(pair.into_inner()),
+        _ => unreachable!(),
+    }
+}
+
+fn get_children_token_stream(children_pair: Pair<Rule>) -> TokenStream {
+    let mut children = vec![];
+    for p in children_pair.into_inner() {
+        let token = match p.as_rule() {
+            Rule::tag | Rule::fragment => {
+                let _tag = get_node_token_stream(p);
+                quote! {
+                    _squark::Child::from($_tag),
+                }
+            }
+            Rule::text => {
+                let _text = TokenTree::Literal(Literal::string(p.as_str()));
+                quote! {
+                    $_text.into(),
+                }
+            }
+            Rule::embedded => {
+                let _embedded = TokenStream::from_str(p.as_str()).unwrap();
+                quote! {
+                    {$_embedded}.into(),
+                }
+            }
+            _ => unreachable!(),
+        };
+        children.push(token);
+    }
+    TokenStream::from_iter(children)
+}
+
 #[proc_macro]
 pub fn view(arg: TokenStream) -> TokenStream {
     let s = arg.to_string();
     let mut pairs = ViewParser::parse(Rule::view, &s).unwrap();
-    let _token = get_token_stream(pairs.next().unwrap().into_inner());
+    let _token = get_node_token_stream(pairs.next().unwrap());
 
     quote! {
         {
@@ -3,7 +3,7 @@
 extern crate squark;
 extern crate squark_macros;
 
-use squark::View;
+use squark::{App, HandlerArg, Runtime, Task, TestRuntime, View};
 use squark_macros::view;
 
 fn v() -> View<()> {
@@ -29,7 +29,64 @@ fn v() -> View<()> {
     }
 }
 
+fn fragment() -> View<()> {
+    view! {
+        <>
+            <li>first</li>
+            <>
+                <li>second</li>
+                { "third" }
+            </>
+        </>
+    }
+}
+
 #[test]
 fn it_works() {
     let v = v();
 }
+
+#[test]
+fn render_fragment() {
+    assert_eq!(fragment().to_html(), "<li>first</li><li>second</li>third");
+}
+
+#[test]
+fn render_property() {
+    let v: View<()> = view! { <input type="checkbox" prop:checked={ true } prop:value="on" /> };
+    assert_eq!(v.to_html(), "<input type=\"checkbox\" checked value=\"on\">");
+}
+
+#[derive(Clone, Default)]
+struct FormApp;
+
+impl App for FormApp {
+    type State = u32;
+    type Action = ();
+
+    fn reducer(&self, state: u32, _: ()) -> (u32, Task<()>) {
+        (state + 1, Task::empty())
+    }
+
+    fn view(&self, _: u32) -> View<()> {
+        view! {
+            <form onsubmit|prevent|stop={ |_| Some(()) }>
+                <button onclick|once={ |_| Some(()) } />
+            </form>
+        }
+    }
+}
+
+#[test]
+fn listener_options() {
+    let runtime = TestRuntime::<FormApp>::new(0);
+    runtime.run();
+    assert_eq!(runtime.find("form").unwrap().events(), vec!["submit|prevent|stop"]);
+
+    runtime.fire("form", "submit", HandlerArg::Null);
+    assert_eq!(runtime.state(), 1);
+
+    runtime.fire("button", "click", HandlerArg::Null);
+    assert_eq!(runtime.state(), 2);
+    assert!(runtime.find("button").unwrap().events().is_empty());
+}
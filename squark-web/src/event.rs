@@ -0,0 +1,256 @@
+use serde_json::{json, Map, Value};
+use squark::HandlerArg;
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use js_sys::Array;
+use web_sys::{
+    CompositionEvent, Event, EventTarget, File, FormData, HtmlFormElement, HtmlInputElement,
+    HtmlOptionElement, HtmlSelectElement, HtmlTextAreaElement, KeyboardEvent, MouseEvent,
+    PointerEvent, Touch, TouchEvent, TouchList, WheelEvent,
+};
+
+trait ToHandlerArg: JsCast {
+    fn to_handler_arg(self) -> HandlerArg;
+}
+
+impl ToHandlerArg for Event {
+    fn to_handler_arg(self) -> HandlerArg {
+        json!{null}
+    }
+}
+
+fn file_to_value(file: &File) -> Value {
+    json!{{
+        "name": file.name(),
+        "size": file.size(),
+        "type": file.type_(),
+        "lastModified": file.last_modified(),
+    }}
+}
+
+/// Value of form control which is target of `input` or `change` event.
+/// Checkbox and radio give `checked`, file input gives metadata of files,
+/// and select with `multiple` gives values of all selected options.
+fn control_value(target: Option<EventTarget>) -> HandlerArg {
+    let target = match target {
+        Some(target) => target,
+        None => return json!{""},
+    };
+    if let Some(input) = target.dyn_ref::<HtmlInputElement>() {
+        return match input.type_().as_str() {
+            "checkbox" | "radio" => json!{input.checked()},
+            "file" => {
+                let files = match input.files() {
+                    Some(files) => files,
+                    None => return json!{[]},
+                };
+                let files = (0..files.length()).filter_map(|i| files.item(i));
+                Value::Array(files.map(|file| file_to_value(&file)).collect())
+            }
+            _ => json!{input.value()},
+        };
+    }
+    if let Some(textarea) = target.dyn_ref::<HtmlTextAreaElement>() {
+        return json!{textarea.value()};
+    }
+    if let Some(select) = target.dyn_ref::<HtmlSelectElement>() {
+        if !select.multiple() {
+            return json!{select.value()};
+        }
+        let options = select.selected_options();
+        let values = (0..options.length())
+            .filter_map(|i| options.item(i))
+            .map(|option| json!{option.unchecked_into::<HtmlOptionElement>().value()});
+        return Value::Array(values.collect());
+    }
+    json!{null}
+}
+
+/// Entries of `FormData` of submitted form. Value of name which has multiple entries is given as array.
+fn form_data(target: Option<EventTarget>) -> HandlerArg {
+    let form = match target.and_then(|target| target.dyn_into::<HtmlFormElement>().ok()) {
+        Some(form) => form,
+        None => return json!{null},
+    };
+    let mut map = Map::new();
+    let form_data = FormData::new_with_form(&form).unwrap();
+    let entries = js_sys::try_iter(&form_data).ok().and_then(|entries| entries);
+    for entry in entries.into_iter().flatten().filter_map(Result::ok) {
+        let entry: Array = entry.unchecked_into();
+        let name = entry.get(0).as_string().unwrap_or_default();
+        let value = entry.get(1);
+        let value = match value.as_string() {
+            Some(s) => json!{s},
+            None => file_to_value(value.unchecked_ref()),
+        };
+        match map.get_mut(&name) {
+            Some(Value::Array(values)) => values.push(value),
+            Some(first) => *first = json!{[first.take(), value]},
+            None => {
+                map.insert(name, value);
+            }
+        }
+    }
+    Value::Object(map)
+}
+
+impl ToHandlerArg for KeyboardEvent {
+    fn to_handler_arg(self) -> HandlerArg {
+        json!{{
+            "key": self.key(),
+            "code": self.code(),
+            "location": self.location(),
+            "altKey": self.alt_key(),
+            "ctrlKey": self.ctrl_key(),
+            "metaKey": self.meta_key(),
+            "shiftKey": self.shift_key(),
+            "repeat": self.repeat(),
+            "isComposing": self.is_composing(),
+        }}
+    }
+}
+
+impl ToHandlerArg for CompositionEvent {
+    fn to_handler_arg(self) -> HandlerArg {
+        json!{{
+            "data": self.data(),
+        }}
+    }
+}
+
+/// Fields of `MouseEvent`, which are shared with pointer and wheel events
+fn mouse_fields(ev: &MouseEvent) -> Map<String, Value> {
+    let fields = json!{{
+        "clientX": ev.client_x(),
+        "clientY": ev.client_y(),
+        "pageX": ev.page_x(),
+        "pageY": ev.page_y(),
+        "offsetX": ev.offset_x(),
+        "offsetY": ev.offset_y(),
+        "screenX": ev.screen_x(),
+        "screenY": ev.screen_y(),
+        "movementX": ev.movement_x(),
+        "movementY": ev.movement_y(),
+        "button": ev.button(),
+        "buttons": ev.buttons(),
+        "altKey": ev.alt_key(),
+        "ctrlKey": ev.ctrl_key(),
+        "metaKey": ev.meta_key(),
+        "shiftKey": ev.shift_key(),
+    }};
+    match fields {
+        Value::Object(map) => map,
+        _ => unreachable!(),
+    }
+}
+
+/// Object with fields of `MouseEvent` and `extra` ones
+fn extend_mouse_fields(ev: &MouseEvent, extra: Value) -> HandlerArg {
+    let mut fields = mouse_fields(ev);
+    if let Value::Object(extra) = extra {
+        fields.extend(extra);
+    }
+    Value::Object(fields)
+}
+
+impl ToHandlerArg for MouseEvent {
+    fn to_handler_arg(self) -> HandlerArg {
+        Value::Object(mouse_fields(&self))
+    }
+}
+
+impl ToHandlerArg for PointerEvent {
+    fn to_handler_arg(self) -> HandlerArg {
+        let extra = json!{{
+            "pointerId": self.pointer_id(),
+            "pointerType": self.pointer_type(),
+            "isPrimary": self.is_primary(),
+            "pressure": self.pressure(),
+            "width": self.width(),
+            "height": self.height(),
+            "tiltX": self.tilt_x(),
+            "tiltY": self.tilt_y(),
+        }};
+        extend_mouse_fields(self.as_ref(), extra)
+    }
+}
+
+impl ToHandlerArg for WheelEvent {
+    fn to_handler_arg(self) -> HandlerArg {
+        let extra = json!{{
+            "deltaX": self.delta_x(),
+            "deltaY": self.delta_y(),
+            "deltaZ": self.delta_z(),
+            "deltaMode": self.delta_mode(),
+        }};
+        extend_mouse_fields(self.as_ref(), extra)
+    }
+}
+
+fn touch_to_value(touch: Touch) -> Value {
+    json!{{
+        "identifier": touch.identifier(),
+        "clientX": touch.client_x(),
+        "clientY": touch.client_y(),
+        "pageX": touch.page_x(),
+        "pageY": touch.page_y(),
+        "screenX": touch.screen_x(),
+        "screenY": touch.screen_y(),
+        "radiusX": touch.radius_x(),
+        "radiusY": touch.radius_y(),
+        "force": touch.force(),
+    }}
+}
+
+fn touch_list_to_value(list: TouchList) -> Value {
+    Value::Array((0..list.length()).filter_map(|i| list.item(i)).map(touch_to_value).collect())
+}
+
+impl ToHandlerArg for TouchEvent {
+    fn to_handler_arg(self) -> HandlerArg {
+        json!{{
+            "touches": touch_list_to_value(self.touches()),
+            "targetTouches": touch_list_to_value(self.target_touches()),
+            "changedTouches": touch_list_to_value(self.changed_touches()),
+            "altKey": self.alt_key(),
+            "ctrlKey": self.ctrl_key(),
+            "metaKey": self.meta_key(),
+            "shiftKey": self.shift_key(),
+        }}
+    }
+}
+
+/// Convert event of type `E`, or give null if it is other type such as one made by `new Event("click")`
+fn convert<E: ToHandlerArg>(ev: JsValue) -> HandlerArg {
+    match ev.dyn_into::<E>() {
+        Ok(ev) => ev.to_handler_arg(),
+        Err(ev) => ev.unchecked_into::<Event>().to_handler_arg(),
+    }
+}
+
+/// Convert event into argument of handler.
+/// `input` and `change` give value of form control, and `submit` gives entries of the form.
+/// Keyboard, composition, mouse, pointer, wheel and touch events are given as objects whose fields are named as ones of DOM.
+pub(crate) fn to_handler_arg(name: &str, ev: JsValue) -> HandlerArg {
+    match name {
+        "keydown" | "keyup" | "keypress" => convert::<KeyboardEvent>(ev),
+        "compositionstart" | "compositionupdate" | "compositionend" => {
+            convert::<CompositionEvent>(ev)
+        }
+        "input" | "change" => control_value(ev.unchecked_into::<Event>().target()),
+        "submit" => form_data(ev.unchecked_into::<Event>().target()),
+        "click" | "dblclick" | "auxclick" | "contextmenu" | "mousedown" | "mouseup" | "mousemove"
+        | "mouseover" | "mouseout" | "mouseenter" | "mouseleave" => {
+            convert::<MouseEvent>(ev)
+        }
+        "pointerdown" | "pointerup" | "pointermove" | "pointerover" | "pointerout" | "pointerenter"
+        | "pointerleave" | "pointercancel" | "gotpointercapture" | "lostpointercapture" => {
+            convert::<PointerEvent>(ev)
+        }
+        "wheel" => convert::<WheelEvent>(ev),
+        "touchstart" | "touchmove" | "touchend" | "touchcancel" => {
+            convert::<TouchEvent>(ev)
+        }
+        _ => ev.unchecked_into::<Event>().to_handler_arg(),
+    }
+}
@@ -1,50 +1,58 @@
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::rc::Rc;
-use futures::Future;
-use wasm_bindgen_futures::future_to_promise;
+use futures::future::LocalBoxFuture;
+use wasm_bindgen_futures::spawn_local;
 use squark::{
     uuid,
-    App, AttributeValue, Diff, Element as SquarkElement, Env, HandlerArg, Node as SquarkNode,
-    Runtime,
+    listener_options, property_name, App, AttributeValue, Diff, Element as SquarkElement, Env,
+    Namespace, Node as SquarkNode, Ref, Runtime, Task, TaskError, ESCAPABLE_RAW_TEXT_ELEMENTS,
+    HYDRATION_MARKER, LIFECYCLE_HOOKS,
 };
 use wasm_bindgen::prelude::*;
 use wasm_bindgen::JsCast;
-use web_sys::{window, Document, Element, EventTarget, HtmlElement, Node};
-use serde::Serialize;
-use serde_json::json;
-
-trait ToHandlerArg: JsCast {
-    fn to_handler_arg(self) -> HandlerArg;
+use web_sys::{window, AddEventListenerOptions, Document, Element, EventTarget, HtmlElement, Node};
+
+mod event;
+pub mod sub;
+pub mod task;
+
+use crate::event::to_handler_arg;
+
+/// Detach listener of handler `name`, whose `capture` option has to be same as attached one
+fn remove_listener(target: &EventTarget, name: &str, listener: &Closure<dyn Fn(JsValue)>) {
+    let (event_name, options) = listener_options(name);
+    target
+        .remove_event_listener_with_callback_and_bool(
+            event_name,
+            listener.as_ref().unchecked_ref(),
+            options.capture,
+        )
+        .unwrap();
 }
 
-impl ToHandlerArg for web_sys::Event {
-    fn to_handler_arg(self) -> HandlerArg {
-        json!{null}
-    }
+type AttachedMap = HashMap<String, HashMap<String, Closure<Fn(JsValue)>>>;
+
+/// Lifecycle hooks and refs of element
+#[derive(Default)]
+struct Lifecycle {
+    hooks: HashMap<String, String>,
+    refs: HashMap<String, Ref>,
 }
 
-impl ToHandlerArg for web_sys::InputEvent {
-    fn to_handler_arg(self) -> HandlerArg {
-        let ev: web_sys::Event = self.into();
-        let target = ev.target().unwrap();
-        let js_val: &JsValue = target.as_ref();
-        if js_val.is_null() {
-            return json!{""};
-        }
-        let input_el: &web_sys::HtmlInputElement = target.unchecked_ref();
-        json!{input_el.value()}
-    }
+type LifecycleMap = HashMap<String, Lifecycle>;
+
+/// Access to `web_sys::Element` which `WebRuntime` sets to `Ref`
+pub trait RefExt {
+    fn element(&self) -> Option<Element>;
 }
 
-impl ToHandlerArg for web_sys::KeyboardEvent {
-    fn to_handler_arg(self) -> HandlerArg {
-        json!{self.key()}
+impl RefExt for Ref {
+    fn element(&self) -> Option<Element> {
+        self.get::<Element>()
     }
 }
 
-type AttachedMap = HashMap<String, HashMap<String, Closure<Fn(JsValue)>>>;
-
 fn document() -> Document {
     window().unwrap().document().unwrap()
 }
@@ -62,11 +70,21 @@ fn set_handler_id(el: &HtmlElement, id: &str) {
     js_sys::Reflect::set(el.dataset().as_ref(), &"handlerId".into(), &id.into()).unwrap();
 }
 
+/// Id which attached handlers and lifecycle of element are kept with
+fn get_or_set_handler_id(el: &Element) -> String {
+    get_handler_id(el.unchecked_ref()).unwrap_or_else(|| {
+        let uuid = uuid();
+        set_handler_id(el.unchecked_ref(), &uuid);
+        uuid
+    })
+}
+
 #[derive(Clone)]
 pub struct WebRuntime<A: App> {
     env: Env<A>,
     root: Rc<Element>,
     attached_map: Rc<RefCell<AttachedMap>>,
+    lifecycle_map: Rc<RefCell<LifecycleMap>>,
 }
 
 fn insert_at(parent: &Node, i: usize, node: &Node) {
@@ -78,28 +96,257 @@ fn insert_at(parent: &Node, i: usize, node: &Node) {
     parent.insert_before(&node, ref_node.as_ref()).unwrap();
 }
 
+/// Move with `insert_before` so that moved node keeps its state, such as focus and input value
+fn move_child(parent: &Node, from: usize, to: usize) {
+    let children = parent.child_nodes();
+    let node = children.item(from as u32).unwrap();
+    let ref_node = children.item(if from < to { to + 1 } else { to } as u32);
+    parent.insert_before(&node, ref_node.as_ref()).unwrap();
+}
+
+fn report_mismatch(message: &str) {
+    if cfg!(debug_assertions) {
+        web_sys::console::warn_1(&format!("squark: hydration mismatch: {}", message).into());
+    }
+}
+
+fn is_marker(node: &Node) -> bool {
+    node.node_type() == Node::COMMENT_NODE
+        && node.node_value().map_or(false, |v| v == HYDRATION_MARKER)
+}
+
+fn is_ignorable(node: &Node) -> bool {
+    match node.node_type() {
+        Node::COMMENT_NODE => true,
+        Node::TEXT_NODE => node
+            .node_value()
+            .map_or(true, |v| v.trim().is_empty()),
+        _ => false,
+    }
+}
+
+const XLINK_NAMESPACE: &str = "http://www.w3.org/1999/xlink";
+const XML_NAMESPACE: &str = "http://www.w3.org/XML/1998/namespace";
+
+/// Namespace of prefixed attribute such as `xlink:href`
+fn attribute_namespace(name: &str) -> Option<&'static str> {
+    if name.starts_with("xlink:") {
+        Some(XLINK_NAMESPACE)
+    } else if name.starts_with("xml:") {
+        Some(XML_NAMESPACE)
+    } else {
+        None
+    }
+}
+
 fn set_attribute(el: &Element, name: &str, value: &AttributeValue) {
-    match value {
-        AttributeValue::Bool(b) => {
-            js_sys::Reflect::set(el.as_ref(), &name.into(), &(*b).into()).unwrap();
-            el.set_attribute(name, &b.to_string()).unwrap();
+    match (attribute_namespace(name), value.to_attribute_string(name)) {
+        (None, Some(s)) => el.set_attribute(name, &s).unwrap(),
+        (None, None) => el.remove_attribute(name).unwrap(),
+        (namespace, Some(s)) => el.set_attribute_ns(namespace, name, &s).unwrap(),
+        (namespace, None) => {
+            let local_name = &name[name.find(':').unwrap() + 1..];
+            el.remove_attribute_ns(namespace, local_name).unwrap();
         }
-        AttributeValue::String(s) => {
-            js_sys::Reflect::set(el.as_ref(), &name.into(), &s.into()).unwrap();
-            el.set_attribute(name, s).unwrap();
+    }
+}
+
+fn set_property(el: &Element, name: &str, value: &AttributeValue) {
+    let js_value = match value {
+        AttributeValue::String(s) => s.into(),
+        AttributeValue::Bool(b) => (*b).into(),
+        AttributeValue::Number(n) => (*n).into(),
+        // Class list and style are given as strings, such as to `className`
+        AttributeValue::ClassList(_) | AttributeValue::Style(_) => {
+            value.to_attribute_string(name).unwrap_or_default().into()
         }
+        AttributeValue::None => return reset_property(el, name),
+        // Element is given to `Ref` only by attribute, so `Ref` as property is ignored
+        AttributeValue::Ref(_) => return,
+    };
+    js_sys::Reflect::set(el.as_ref(), &name.into(), &js_value).unwrap();
+}
+
+/// Reset property to its default, which is read from new element of the same name,
+/// such as `false` for `checked`, `""` for `value` and `-1` for `tabIndex` of `<div>`
+fn reset_property(el: &Element, name: &str) {
+    let default = document()
+        .create_element_ns(el.namespace_uri().as_deref(), &el.local_name())
+        .map(|fresh| js_sys::Reflect::get(fresh.as_ref(), &name.into()).unwrap())
+        .unwrap_or(JsValue::UNDEFINED);
+    js_sys::Reflect::set(el.as_ref(), &name.into(), &default).unwrap();
+}
+
+/// Set attribute, or property if it is named with `prop:` prefix
+fn set_attribute_or_property(el: &Element, name: &str, value: &AttributeValue) {
+    match property_name(name) {
+        Some(property) => set_property(el, property, value),
+        None => set_attribute(el, name, value),
     }
 }
 
+fn style(el: &Element) -> web_sys::CssStyleDeclaration {
+    el.unchecked_ref::<HtmlElement>().style()
+}
+
 impl<A: App> WebRuntime<A> {
     pub fn new(root: Element, state: A::State) -> WebRuntime<A> {
         WebRuntime {
             env: Env::new(state),
             root: Rc::new(root),
             attached_map: Rc::new(RefCell::new(AttachedMap::new())),
+            lifecycle_map: Rc::new(RefCell::new(LifecycleMap::new())),
+        }
+    }
+
+    /// Take over markup which is already under root, such as rendered by `Node::to_html` on server.
+    /// Handlers are attached to existing DOM instead of creating whole tree.
+    /// Mismatched nodes are recreated, and reported on console in debug build.
+    pub fn hydrate(&self) {
+        self.hydrate_with_task(Task::empty());
+    }
+
+    /// Hydrate and start `task` as `run_with_task` does, such as to fetch data markup lacks
+    pub fn hydrate_with_task(&self, task: Task<A::Action>) {
+        let node = self.commit_view();
+        self.hydrate_children(self.root.as_ref(), std::slice::from_ref(&node));
+        self.emit_task(task);
+        self.flush_after_render();
+    }
+
+    fn hydrate_children(&self, parent: &Node, children: &[SquarkNode]) {
+        let mut i = 0;
+        for child in SquarkNode::flatten(children) {
+            let mut current = parent.child_nodes().item(i);
+            match child {
+                SquarkNode::Text(s) | SquarkNode::RawHtml(s) => {
+                    if let Some(marker) = current.as_ref().filter(|n| is_marker(n)) {
+                        parent.remove_child(marker).unwrap();
+                        current = parent.child_nodes().item(i);
+                    }
+                    match current {
+                        Some(ref n) if n.node_type() == Node::TEXT_NODE => {
+                            if n.node_value().as_ref() != Some(s) {
+                                report_mismatch(&format!("expected text {:?}", s));
+                                n.set_node_value(Some(s));
+                            }
+                        }
+                        _ => {
+                            if !s.is_empty() {
+                                report_mismatch(&format!("missing text {:?}", s));
+                            }
+                            let text = document().create_text_node(s.as_str());
+                            insert_at(parent, i as usize, text.as_ref());
+                        }
+                    }
+                }
+                SquarkNode::Element(el) => {
+                    while let Some(n) = current.as_ref().filter(|n| is_ignorable(n)) {
+                        parent.remove_child(n).unwrap();
+                        current = parent.child_nodes().item(i);
+                    }
+                    match current {
+                        Some(ref n) if n.is_instance_of::<Element>() && {
+                            let web_el: &Element = n.unchecked_ref();
+                            web_el.local_name() == el.name()
+                                && web_el.namespace_uri().as_deref() == Some(el.namespace().uri())
+                        } =>
+                        {
+                            self.hydrate_element(n.unchecked_ref(), el);
+                        }
+                        _ => {
+                            report_mismatch(&format!("expected <{}>", el.name()));
+                            let web_el = self.create_element(el);
+                            match current {
+                                Some(_) => self.replace_at(parent, i as usize, web_el.as_ref()),
+                                None => insert_at(parent, i as usize, web_el.as_ref()),
+                            }
+                        }
+                    }
+                }
+                SquarkNode::Fragment(_) | SquarkNode::Lazy(_) | SquarkNode::Null => continue,
+            }
+            i += 1;
+        }
+
+        while let Some(n) = parent.child_nodes().item(i) {
+            if !is_ignorable(&n) {
+                report_mismatch("unexpected node remains");
+            }
+            self.remove_attached(&n);
+            parent.remove_child(&n).unwrap();
+        }
+    }
+
+    /// Content of `<textarea>` and `<title>` is parsed as one text node without hydration markers,
+    /// so it is rebuilt as nodes of `children` for later diffs
+    fn hydrate_text_children(&self, parent: &Node, children: &[SquarkNode]) {
+        let children = SquarkNode::flatten(children);
+        let expected: String = children
+            .iter()
+            .filter_map(|child| match child {
+                SquarkNode::Text(s) | SquarkNode::RawHtml(s) => Some(s.as_str()),
+                _ => None,
+            })
+            .collect();
+        if parent.text_content().unwrap_or_default() != expected {
+            report_mismatch(&format!("expected text {:?}", expected));
+        }
+        while let Some(n) = parent.first_child() {
+            parent.remove_child(&n).unwrap();
+        }
+        for child in children {
+            match child {
+                SquarkNode::Text(s) | SquarkNode::RawHtml(s) => {
+                    let text = document().create_text_node(s.as_str());
+                    parent.append_child(text.as_ref()).unwrap();
+                }
+                SquarkNode::Element(el) => {
+                    parent.append_child(self.create_element(el).as_ref()).unwrap();
+                }
+                SquarkNode::Fragment(_) | SquarkNode::Lazy(_) | SquarkNode::Null => (),
+            }
         }
     }
 
+    fn hydrate_element(&self, web_el: &Element, el: &SquarkElement) {
+        for (ref name, ref value) in el.attributes() {
+            if let AttributeValue::Ref(r) = value {
+                self.set_ref(web_el, name, r);
+                continue;
+            }
+            if let Some(property) = property_name(name) {
+                set_property(web_el, property, value);
+                continue;
+            }
+            if web_el.get_attribute(name) != value.to_attribute_string(name) {
+                report_mismatch(&format!("attribute {} of <{}>", name, el.name()));
+                set_attribute(web_el, name, value);
+            }
+        }
+
+        for (ref name, id) in el.handlers() {
+            self.set_handler(web_el, name, &id);
+        }
+
+        match el.raw_html() {
+            _ if el.is_unmanaged() => (),
+            Some(html) => {
+                // Serialized markup can differ from source, so it is not reported as mismatch
+                if web_el.inner_html() != html {
+                    web_el.set_inner_html(html);
+                }
+            }
+            None if el.namespace() == Namespace::Html
+                && ESCAPABLE_RAW_TEXT_ELEMENTS.contains(&el.name()) =>
+            {
+                self.hydrate_text_children(web_el.as_ref(), el.children())
+            }
+            None => self.hydrate_children(web_el.as_ref(), el.children()),
+        }
+        self.call_element_hook(web_el, "mount");
+    }
+
     fn handle_diff_inner(&self, el: &Element, diff: Diff) {
         match diff {
             Diff::AddChild(i, node) => self.add_child(el, i, node),
@@ -109,25 +356,56 @@ impl<A: App> WebRuntime<A> {
                 for diff in diffs {
                     self.handle_diff_inner(child.unchecked_ref(), diff);
                 }
+                self.call_element_hook(child.unchecked_ref(), "update");
             }
             Diff::ReplaceChild(i, node) => self.replace_child(el, i, node),
+            Diff::SetAttribute(name, AttributeValue::Ref(r)) => self.set_ref(el, &name, &r),
             Diff::SetAttribute(name, value) => set_attribute(el, &name, &value),
             Diff::RemoveAttribute(name) => {
-                el.remove_attribute(&name).unwrap();
+                if !self.remove_ref(el, &name) {
+                    set_attribute(el, &name, &AttributeValue::None);
+                }
+            }
+            Diff::SetProperty(name, value) => set_property(el, &name, &value),
+            Diff::RemoveProperty(name) => reset_property(el, &name),
+            // Empty class or one with whitespace is rejected by DOM, and it is ignored
+            Diff::AddClass(class) => {
+                let _ = el.class_list().add_1(&class);
+            }
+            Diff::RemoveClass(class) => {
+                let _ = el.class_list().remove_1(&class);
+            }
+            Diff::SetStyle(property, value) => style(el).set_property(&property, &value).unwrap(),
+            Diff::RemoveStyle(property) => {
+                style(el).remove_property(&property).unwrap();
             }
             Diff::RemoveChild(i) => self.remove_child(el.as_ref(), i),
+            Diff::MoveChild(from, to) => move_child(el.as_ref(), from, to),
             Diff::SetHandler(name, id) => self.set_handler(el.unchecked_ref(), &name, &id),
+            Diff::RemoveHandler(ref name, _) if LIFECYCLE_HOOKS.contains(&name.as_str()) => {
+                let id = get_handler_id(el.unchecked_ref()).unwrap();
+                if let Some(lifecycle) = self.lifecycle_map.borrow_mut().get_mut(&id) {
+                    lifecycle.hooks.remove(name);
+                }
+            }
             Diff::RemoveHandler(name, _) => {
                 let attached = self
                     .attached_map
                     .borrow_mut()
                     .get_mut(&get_handler_id(el.unchecked_ref()).unwrap())
-                    .and_then(|inner| inner.remove(&name))
-                    .unwrap();
-                let html_el: &EventTarget = el.unchecked_ref();
-                html_el
-                    .remove_event_listener_with_callback(&name, attached.as_ref().unchecked_ref())
-                    .unwrap();
+                    .and_then(|inner| inner.remove(&name));
+                // Listener with `once` option is already removed when it is called
+                if let Some(attached) = attached {
+                    remove_listener(el.as_ref(), &name, &attached);
+                }
+            }
+            Diff::SetInnerHtml(html) => {
+                let as_node: &Node = el.as_ref();
+                let children = as_node.child_nodes();
+                for i in 0..children.length() {
+                    self.remove_attached(&children.item(i).unwrap());
+                }
+                el.set_inner_html(&html);
             }
         }
     }
@@ -139,24 +417,34 @@ impl<A: App> WebRuntime<A> {
     }
 
     fn create_element(&self, el: &SquarkElement) -> Element {
-        let web_el: Element = document().create_element(el.name()).unwrap();
+        let web_el: Element = match el.namespace() {
+            Namespace::Html => document().create_element(el.name()).unwrap(),
+            namespace => document()
+                .create_element_ns(Some(namespace.uri()), el.name())
+                .unwrap(),
+        };
         for (ref name, ref value) in el.attributes() {
-            set_attribute(&web_el, name, value);
+            match value {
+                AttributeValue::Ref(r) => self.set_ref(&web_el, name, r),
+                _ => set_attribute_or_property(&web_el, name, value),
+            }
         }
 
         for (ref name, id) in el.handlers() {
             self.set_handler(web_el.unchecked_ref(), name, &id);
         }
 
-        {
+        if let Some(html) = el.raw_html() {
+            web_el.set_inner_html(html);
+        } else {
             let node: &Node = web_el.as_ref();
-            for child in el.children() {
+            for child in SquarkNode::flatten(el.children()) {
                 match child {
                     SquarkNode::Element(el) => {
                         let child = self.create_element(el);
                         node.append_child(child.as_ref()).unwrap();
                     }
-                    SquarkNode::Text(s) => {
+                    SquarkNode::Text(s) | SquarkNode::RawHtml(s) => {
                         let child = document().create_text_node(s.as_str());
                         node.append_child(child.as_ref()).unwrap();
                     }
@@ -165,6 +453,7 @@ impl<A: App> WebRuntime<A> {
             }
         }
 
+        self.call_element_hook(&web_el, "mount");
         web_el
     }
 
@@ -174,7 +463,7 @@ impl<A: App> WebRuntime<A> {
                 let child = self.create_element(&el);
                 insert_at(parent.as_ref(), i, child.as_ref());
             }
-            SquarkNode::Text(s) => {
+            SquarkNode::Text(s) | SquarkNode::RawHtml(s) => {
                 let child = document().create_text_node(s.as_str());
                 insert_at(parent.as_ref(), i, child.as_ref());
             }
@@ -188,7 +477,7 @@ impl<A: App> WebRuntime<A> {
                 let child = self.create_element(&el);
                 self.replace_at(parent.as_ref(), i, child.as_ref());
             }
-            SquarkNode::Text(s) => {
+            SquarkNode::Text(s) | SquarkNode::RawHtml(s) => {
                 let child = document().create_text_node(s.as_str());
                 self.replace_at(parent.as_ref(), i, child.as_ref());
             }
@@ -204,69 +493,138 @@ impl<A: App> WebRuntime<A> {
     }
 
     fn set_handler(&self, el: &Element, name: &str, id: &str) {
-        let closure = match name {
-            "keydown" => self._set_handler::<web_sys::KeyboardEvent>(el.as_ref(), "keydown", id),
-            "input" => self._set_handler::<web_sys::InputEvent>(el.as_ref(), "input", id),
-            name => self._set_handler::<web_sys::Event>(el.as_ref(), name, id),
-        };
+        if LIFECYCLE_HOOKS.contains(&name) {
+            let handler_id = get_or_set_handler_id(el);
+            let mut map = self.lifecycle_map.borrow_mut();
+            let lifecycle = map.entry(handler_id).or_insert_with(Lifecycle::default);
+            lifecycle.hooks.insert(name.to_owned(), id.to_owned());
+            return;
+        }
 
-        let handler_id = get_handler_id(el.unchecked_ref()).unwrap_or_else(|| {
-            let uuid = uuid();
-            set_handler_id(el.unchecked_ref(), &uuid);
-            uuid
-        });
+        let handler_id = get_or_set_handler_id(el);
+        let closure = self._set_handler(el.as_ref(), name, id, &handler_id);
 
         let mut map = self.attached_map.borrow_mut();
         let inner = map.entry(handler_id).or_insert_with(HashMap::new);
         if let Some(attached) = inner.remove(name) {
-            let target: &EventTarget = el.as_ref();
-            target
-                .remove_event_listener_with_callback(&name, attached.as_ref().unchecked_ref())
-                .unwrap();
+            remove_listener(el.as_ref(), name, &attached);
         }
         inner.insert(name.to_owned(), closure);
     }
 
-    fn _set_handler<T: ToHandlerArg>(
+    fn _set_handler(
         &self,
         el: &EventTarget,
         name: &str,
         id: &str,
+        handler_id: &str,
     ) -> Closure<Fn(JsValue)> {
-        let handler = self.pop_handler(id).unwrap();
+        let handler = self.listener(id);
+        let (event_name, options) = listener_options(name);
+        let event_name = event_name.to_owned();
+        let key = name.to_owned();
+        let handler_id = handler_id.to_owned();
+        let attached_map = self.attached_map.clone();
         let closure = Closure::new(move |ev: JsValue| {
-            let ev: T = ev.unchecked_into();
-            handler(ev.to_handler_arg());
+            // Browser removes listener with `once` option, so drop it as well not to detach it later
+            let spent = if options.once {
+                attached_map.borrow_mut().get_mut(&handler_id).and_then(|inner| inner.remove(&key))
+            } else {
+                None
+            };
+            let event: &web_sys::Event = ev.unchecked_ref();
+            if options.prevent {
+                event.prevent_default();
+            }
+            if options.stop {
+                event.stop_propagation();
+            }
+            handler(to_handler_arg(&event_name, ev));
+            drop(spent);
         });
-        el.add_event_listener_with_callback(name, closure.as_ref().unchecked_ref())
-            .unwrap();
+        let add_options = AddEventListenerOptions::new();
+        add_options.set_capture(options.capture);
+        add_options.set_passive(options.passive);
+        add_options.set_once(options.once);
+        el.add_event_listener_with_callback_and_add_event_listener_options(
+            listener_options(name).0,
+            closure.as_ref().unchecked_ref(),
+            &add_options,
+        )
+        .unwrap();
         closure
     }
 
+    fn set_ref(&self, el: &Element, name: &str, r: &Ref) {
+        r.set(el.to_owned());
+        let handler_id = get_or_set_handler_id(el);
+        let mut map = self.lifecycle_map.borrow_mut();
+        let lifecycle = map.entry(handler_id).or_insert_with(Lifecycle::default);
+        if let Some(old) = lifecycle.refs.insert(name.to_owned(), r.to_owned()) {
+            if &old != r {
+                old.clear();
+            }
+        }
+    }
+
+    /// Clear ref given as attribute `name`, and return whether there was the ref
+    fn remove_ref(&self, el: &Element, name: &str) -> bool {
+        let removed = get_handler_id(el.unchecked_ref()).and_then(|id| {
+            let mut map = self.lifecycle_map.borrow_mut();
+            map.get_mut(&id).and_then(|lifecycle| lifecycle.refs.remove(name))
+        });
+        match removed {
+            Some(r) => {
+                r.clear();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Call lifecycle hook `name` of element after render
+    fn call_element_hook(&self, el: &Element, name: &str) {
+        let id = get_handler_id(el.unchecked_ref()).and_then(|handler_id| {
+            let map = self.lifecycle_map.borrow();
+            map.get(&handler_id).and_then(|lifecycle| lifecycle.hooks.get(name).cloned())
+        });
+        if let Some(id) = id {
+            self.call_hook(name, &id);
+        }
+    }
+
+    /// Detach handlers of removed element and its descendants, clear their refs and call `unmount` hooks
     fn remove_attached(&self, el: &Node) {
         if !el.is_instance_of::<Element>() {
             return;
         }
 
         let el: &Element = el.unchecked_ref();
-
-        let mut map = self.attached_map.borrow_mut();
-        if let Some(id) = get_handler_id(el.unchecked_ref()) {
-            map.remove(&id);
-        }
-
-        let children = el.query_selector_all("[data-has-handler]").unwrap();
+        let mut handler_ids: Vec<String> = get_handler_id(el.unchecked_ref()).into_iter().collect();
+        let children = el.query_selector_all("[data-handler-id]").unwrap();
         for i in 0..children.length() {
             let child = children.item(i).unwrap();
-            if let Some(id) = get_handler_id(child.unchecked_ref()) {
-                map.remove(&id);
+            handler_ids.extend(get_handler_id(child.unchecked_ref()));
+        }
+
+        let mut map = self.attached_map.borrow_mut();
+        let mut lifecycle_map = self.lifecycle_map.borrow_mut();
+        for handler_id in handler_ids {
+            map.remove(&handler_id);
+            let lifecycle = match lifecycle_map.remove(&handler_id) {
+                Some(lifecycle) => lifecycle,
+                None => continue,
+            };
+            for r in lifecycle.refs.values() {
+                r.clear();
+            }
+            if let Some(id) = lifecycle.hooks.get("unmount") {
+                self.call_hook("unmount", id);
             }
         }
     }
 }
 
-fn nop<T>(_: T) {}
-
 impl<A: App> Runtime<A> for WebRuntime<A> {
     fn get_env<'a>(&'a self) -> &'a Env<A> {
         &self.env
@@ -288,14 +646,11 @@ impl<A: App> Runtime<A> for WebRuntime<A> {
         self.handle_diff_inner(&self.root, diff);
     }
 
-    fn handle_future<T: Serialize + 'static, E: Serialize + 'static>(&self, future: Box<Future<Item = T, Error = E>>) {
-        let p = future_to_promise(
-            future
-                .map(|v| JsValue::from_serde(&v).unwrap())
-                .map_err(|e| JsValue::from_serde(&e).unwrap())
-        );
-        let closure = Closure::new(nop);
-        p.then(&closure);
-        closure.forget();
+    fn handle_future(&self, future: LocalBoxFuture<'static, ()>) {
+        spawn_local(future);
+    }
+
+    fn report_error(&self, error: TaskError) {
+        web_sys::console::error_1(&format!("squark: unhandled task error: {}", error).into());
     }
 }
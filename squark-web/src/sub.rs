@@ -0,0 +1,103 @@
+use std::cell::Cell;
+use std::rc::Rc;
+use squark::{HandlerArg, Sub, Unsubscribe};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+use web_sys::{window, EventTarget};
+use serde_json::json;
+
+use crate::document;
+use crate::event::to_handler_arg;
+
+/// Emits `null` every `ms` milliseconds.
+pub fn interval<A, F>(ms: i32, f: F) -> Sub<A>
+where
+    F: Fn(HandlerArg) -> Option<A> + 'static,
+{
+    let subscriber = move |emit: Box<dyn Fn(HandlerArg)>| -> Unsubscribe {
+        let closure = Closure::wrap(Box::new(move || {
+            emit(json!{null});
+        }) as Box<dyn Fn()>);
+        let handle = window()
+            .unwrap()
+            .set_interval_with_callback_and_timeout_and_arguments_0(
+                closure.as_ref().unchecked_ref(),
+                ms,
+            )
+            .unwrap();
+        Box::new(move || {
+            window().unwrap().clear_interval_with_handle(handle);
+            drop(closure);
+        })
+    };
+    Sub::new(format!("interval:{}", ms), subscriber, f)
+}
+
+fn request_frame(emit: Rc<dyn Fn(HandlerArg)>, cancelled: Rc<Cell<bool>>) {
+    let closure = Closure::once_into_js(move |timestamp: f64| {
+        if cancelled.get() {
+            return;
+        }
+        emit(json!{timestamp});
+        if cancelled.get() {
+            return;
+        }
+        request_frame(emit, cancelled);
+    });
+    window()
+        .unwrap()
+        .request_animation_frame(closure.unchecked_ref())
+        .unwrap();
+}
+
+/// Emits timestamp of `requestAnimationFrame` on every frame.
+pub fn animation_frame<A, F>(f: F) -> Sub<A>
+where
+    F: Fn(HandlerArg) -> Option<A> + 'static,
+{
+    let subscriber = |emit: Box<dyn Fn(HandlerArg)>| -> Unsubscribe {
+        let cancelled = Rc::new(Cell::new(false));
+        request_frame(Rc::from(emit), cancelled.clone());
+        // Pending frame is left to be fired once, so that its closure is freed
+        Box::new(move || cancelled.set(true))
+    };
+    Sub::new("animation_frame".to_owned(), subscriber, f)
+}
+
+fn listen<A, F>(key: String, target: EventTarget, name: &str, f: F) -> Sub<A>
+where
+    F: Fn(HandlerArg) -> Option<A> + 'static,
+{
+    let name = name.to_owned();
+    let subscriber = move |emit: Box<dyn Fn(HandlerArg)>| -> Unsubscribe {
+        let event_name = name.clone();
+        let closure = Closure::wrap(Box::new(move |ev: JsValue| {
+            emit(to_handler_arg(&event_name, ev));
+        }) as Box<dyn Fn(JsValue)>);
+        target
+            .add_event_listener_with_callback(&name, closure.as_ref().unchecked_ref())
+            .unwrap();
+        Box::new(move || {
+            target
+                .remove_event_listener_with_callback(&name, closure.as_ref().unchecked_ref())
+                .unwrap();
+        })
+    };
+    Sub::new(key, subscriber, f)
+}
+
+/// Listens event such as `resize` on `window`.
+pub fn window_event<A, F>(name: &str, f: F) -> Sub<A>
+where
+    F: Fn(HandlerArg) -> Option<A> + 'static,
+{
+    listen(format!("window:{}", name), window().unwrap().into(), name, f)
+}
+
+/// Listens event such as `keydown` on `document`.
+pub fn document_event<A, F>(name: &str, f: F) -> Sub<A>
+where
+    F: Fn(HandlerArg) -> Option<A> + 'static,
+{
+    listen(format!("document:{}", name), document().into(), name, f)
+}
@@ -0,0 +1,89 @@
+use squark::{Ref, Task};
+use wasm_bindgen::JsCast;
+use web_sys::{Element, HtmlElement, HtmlInputElement, HtmlTextAreaElement};
+
+use crate::{document, RefExt};
+
+/// Element which command is given to, found by CSS selector or `Ref`
+pub enum Target {
+    Selector(String),
+    Ref(Ref),
+}
+
+impl<'a> From<&'a str> for Target {
+    fn from(selector: &'a str) -> Target {
+        Target::Selector(selector.to_owned())
+    }
+}
+
+impl From<String> for Target {
+    fn from(selector: String) -> Target {
+        Target::Selector(selector)
+    }
+}
+
+impl From<Ref> for Target {
+    fn from(r: Ref) -> Target {
+        Target::Ref(r)
+    }
+}
+
+impl<'a> From<&'a Ref> for Target {
+    fn from(r: &'a Ref) -> Target {
+        Target::Ref(r.to_owned())
+    }
+}
+
+impl Target {
+    fn element(&self) -> Option<Element> {
+        match self {
+            Target::Selector(selector) => document().query_selector(selector).ok().and_then(|el| el),
+            Target::Ref(r) => r.element(),
+        }
+    }
+}
+
+/// Call `f` with target element after next render is committed.
+/// Nothing is done if no element is found.
+fn command<A, T, F>(target: T, f: F) -> Task<A>
+where
+    A: 'static,
+    T: Into<Target>,
+    F: FnOnce(Element) + 'static,
+{
+    let target = target.into();
+    Task::after_render(move || {
+        if let Some(el) = target.element() {
+            f(el);
+        }
+    })
+}
+
+/// Focus element after next render, such as input which is rendered by the render.
+pub fn focus<A: 'static, T: Into<Target>>(target: T) -> Task<A> {
+    command(target, |el| {
+        el.unchecked_ref::<HtmlElement>().focus().unwrap();
+    })
+}
+
+pub fn blur<A: 'static, T: Into<Target>>(target: T) -> Task<A> {
+    command(target, |el| {
+        el.unchecked_ref::<HtmlElement>().blur().unwrap();
+    })
+}
+
+pub fn scroll_into_view<A: 'static, T: Into<Target>>(target: T) -> Task<A> {
+    command(target, |el| el.scroll_into_view())
+}
+
+/// Select text from `start` to `end` of input or textarea, or move cursor to `start` if they are same.
+pub fn set_selection_range<A: 'static, T: Into<Target>>(target: T, start: u32, end: u32) -> Task<A> {
+    command(target, move |el| {
+        if let Some(input) = el.dyn_ref::<HtmlInputElement>() {
+            // Some types of input such as `number` don't support selection
+            let _ = input.set_selection_range(start, end);
+        } else if let Some(textarea) = el.dyn_ref::<HtmlTextAreaElement>() {
+            textarea.set_selection_range(start, end).unwrap();
+        }
+    })
+}
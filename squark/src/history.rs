@@ -0,0 +1,142 @@
+use crate::{App, Env, Runtime};
+
+/// Record of dispatched actions and states following them.
+pub(crate) struct History<A: App> {
+    initial: A::State,
+    entries: Vec<(A::Action, A::State)>,
+    position: usize,
+    paused: bool,
+    held: Vec<A::Action>,
+}
+
+impl<A: App> History<A> {
+    fn new(initial: A::State) -> History<A> {
+        History {
+            initial,
+            entries: vec![],
+            position: 0,
+            paused: false,
+            held: vec![],
+        }
+    }
+
+    fn state_at(&self, position: usize) -> A::State {
+        match position {
+            0 => self.initial.to_owned(),
+            i => self.entries[i - 1].1.to_owned(),
+        }
+    }
+}
+
+impl<A: App> Env<A> {
+    /// Hold `action` instead of reducing it while history is paused
+    pub(crate) fn hold_if_paused(&self, action: &A::Action) -> bool {
+        match *self.history.borrow_mut() {
+            Some(ref mut history) if history.paused => {
+                history.held.push(action.to_owned());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn record(&self, action: A::Action, state: &A::State) {
+        if let Some(ref mut history) = *self.history.borrow_mut() {
+            history.entries.push((action, state.to_owned()));
+            history.position = history.entries.len();
+        }
+    }
+}
+
+/// Time-travel debugging on any runtime.
+///
+/// Position `0` is the state when recording is started, and position `n` is the state after `n`th action.
+pub trait TimeTravel<A: App>: Runtime<A> {
+    fn enable_history(&self) {
+        let env = self.get_env();
+        *env.history.borrow_mut() = Some(History::new(env.get_state()));
+    }
+
+    fn disable_history(&self) {
+        self.resume();
+        *self.get_env().history.borrow_mut() = None;
+    }
+
+    /// Recorded actions and states following them
+    fn history(&self) -> Vec<(A::Action, A::State)> {
+        self.get_env()
+            .history
+            .borrow()
+            .as_ref()
+            .map_or_else(Vec::new, |h| h.entries.to_owned())
+    }
+
+    /// Current position in history, which is changed by `jump_to`
+    fn history_position(&self) -> Option<usize> {
+        self.get_env().history.borrow().as_ref().map(|h| h.position)
+    }
+
+    /// Render the state at `position`. History is paused so that live actions don't branch from past state.
+    fn jump_to(&self, position: usize) {
+        let state = {
+            let mut history = self.get_env().history.borrow_mut();
+            let history = match *history {
+                Some(ref mut history) => history,
+                None => return,
+            };
+            let position = position.min(history.entries.len());
+            history.position = position;
+            history.paused = true;
+            history.state_at(position)
+        };
+        self.set_state(state);
+    }
+
+    /// Hold live actions until `resume` is called
+    fn pause(&self) {
+        if let Some(ref mut history) = *self.get_env().history.borrow_mut() {
+            history.paused = true;
+        }
+    }
+
+    /// Go back to latest state, and dispatch actions held while paused
+    fn resume(&self) {
+        let (state, held) = {
+            let mut history = self.get_env().history.borrow_mut();
+            let history = match *history {
+                Some(ref mut history) => history,
+                None => return,
+            };
+            history.paused = false;
+            history.position = history.entries.len();
+            (history.state_at(history.position), history.held.split_off(0))
+        };
+        self.set_state(state);
+        for action in held {
+            self.on_action(action);
+        }
+    }
+
+    /// Run reducer again for all recorded actions from initial state, such as after reducer is modified.
+    /// Tasks emitted while replaying are dropped.
+    fn replay(&self) {
+        let env = self.get_env();
+        let state = {
+            let mut history = env.history.borrow_mut();
+            let history = match *history {
+                Some(ref mut history) => history,
+                None => return,
+            };
+            let mut state = history.initial.to_owned();
+            for entry in history.entries.iter_mut() {
+                let (new_state, _) = env.app.reducer(state, entry.0.to_owned());
+                entry.1 = new_state.to_owned();
+                state = new_state;
+            }
+            history.state_at(history.position)
+        };
+        self.set_state(state);
+    }
+}
+
+impl<A: App, R: Runtime<A>> TimeTravel<A> for R {}
@@ -0,0 +1,194 @@
+use std::fmt::{self, Write};
+use std::io;
+
+use crate::vdom::{property_name, AttributeValue, Element, Namespace, Node, View};
+
+/// Content of comment which is put before a text node when the browser would merge it to
+/// previous text node or drop it as empty. Hydration removes these comments.
+pub const HYDRATION_MARKER: &str = "squark-text";
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+/// Elements whose content is parsed as one text node, so hydration marker can't be put in them
+pub const ESCAPABLE_RAW_TEXT_ELEMENTS: &[&str] = &["textarea", "title"];
+
+/// Properties which are written as attributes of the same name, which give their initial values.
+/// Other properties such as `innerHTML` have no attribute, so they are skipped.
+const REFLECTED_PROPERTIES: &[&str] = &["value", "checked", "selected"];
+
+fn write_escaped<W: Write>(w: &mut W, s: &str, escape_quote: bool) -> fmt::Result {
+    let mut last = 0;
+    for (i, c) in s.char_indices() {
+        let escaped = match c {
+            '&' => "&amp;",
+            '<' => "&lt;",
+            '>' => "&gt;",
+            '"' if escape_quote => "&quot;",
+            _ => continue,
+        };
+        w.write_str(&s[last..i])?;
+        w.write_str(escaped)?;
+        last = i + 1;
+    }
+    w.write_str(&s[last..])
+}
+
+/// Write text of `<script>` or `<style>` without escaping, except for `</` of end tag of `name`
+/// which is written as `<\/`, such as `</script>` in string literal
+fn write_raw_text<W: Write>(w: &mut W, s: &str, name: &str) -> fmt::Result {
+    let mut last = 0;
+    for (i, _) in s.match_indices("</") {
+        let rest = &s.as_bytes()[i + 2..];
+        if rest.len() >= name.len() && rest[..name.len()].eq_ignore_ascii_case(name.as_bytes()) {
+            w.write_str(&s[last..i])?;
+            w.write_str("<\\/")?;
+            last = i + 2;
+        }
+    }
+    w.write_str(&s[last..])
+}
+
+/// Children of raw text element `name`. Consecutive texts are joined before written,
+/// so that end tag split into them is also found. Raw HTML is written as it is.
+fn write_raw_text_children<W: Write>(w: &mut W, children: &[Node], name: &str) -> fmt::Result {
+    let mut text = String::new();
+    for child in Node::flatten(children) {
+        match child {
+            Node::Text(s) => text.push_str(s),
+            Node::RawHtml(s) => {
+                write_raw_text(w, &text, name)?;
+                text.clear();
+                w.write_str(s)?;
+            }
+            Node::Element(el) => {
+                write_raw_text(w, &text, name)?;
+                text.clear();
+                write_element(w, el)?;
+            }
+            Node::Fragment(_) | Node::Lazy(_) | Node::Null => (),
+        }
+    }
+    write_raw_text(w, &text, name)
+}
+
+/// Content of `<textarea>` and `<title>` is parsed as one text node, so no hydration marker is
+/// written into it when `escapable_raw_text` is given
+fn write_children<W: Write>(w: &mut W, children: &[Node], escapable_raw_text: bool) -> fmt::Result {
+    let mut after_text = false;
+    for child in Node::flatten(children) {
+        match child {
+            // Raw HTML which is not the only child is rendered as text
+            Node::Text(s) | Node::RawHtml(s) => {
+                if !escapable_raw_text && (after_text || s.is_empty()) {
+                    write!(w, "<!--{}-->", HYDRATION_MARKER)?;
+                }
+                write_escaped(w, s, false)?;
+                after_text = true;
+            }
+            Node::Element(el) => {
+                write_element(w, el)?;
+                after_text = false;
+            }
+            Node::Fragment(_) | Node::Lazy(_) | Node::Null => (),
+        }
+    }
+    Ok(())
+}
+
+fn write_element<W: Write>(w: &mut W, el: &Element) -> fmt::Result {
+    write!(w, "<{}", el.name())?;
+    for &(ref name, ref value) in el.attributes() {
+        let name = match property_name(name) {
+            Some(property) if REFLECTED_PROPERTIES.contains(&property) => property,
+            Some(_) => continue,
+            None => name,
+        };
+        match value.to_attribute_string(name) {
+            None => (),
+            // Boolean attribute is written by its name only
+            Some(ref s) if s.is_empty() && value == &AttributeValue::Bool(true) => {
+                write!(w, " {}", name)?
+            }
+            Some(s) => {
+                write!(w, " {}=\"", name)?;
+                write_escaped(w, &s, true)?;
+                w.write_char('"')?;
+            }
+        }
+    }
+    w.write_char('>')?;
+
+    // Void and raw text elements are only of HTML, and elements of SVG and MathML are always closed
+    let is_html = el.namespace() == Namespace::Html;
+    if is_html && VOID_ELEMENTS.contains(&el.name()) {
+        return Ok(());
+    }
+
+    match el.raw_html() {
+        Some(html) => w.write_str(html)?,
+        None if is_html && RAW_TEXT_ELEMENTS.contains(&el.name()) => {
+            write_raw_text_children(w, el.children(), el.name())?
+        }
+        None => {
+            let escapable_raw_text = is_html && ESCAPABLE_RAW_TEXT_ELEMENTS.contains(&el.name());
+            write_children(w, el.children(), escapable_raw_text)?
+        }
+    }
+    write!(w, "</{}>", el.name())
+}
+
+struct IoWriter<W: io::Write> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> Write for IoWriter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+impl Node {
+    /// Render as HTML which `squark-web` can hydrate.
+    pub fn to_html(&self) -> String {
+        let mut s = String::new();
+        self.write_html(&mut s).unwrap();
+        s
+    }
+
+    pub fn write_html<W: Write>(&self, w: &mut W) -> fmt::Result {
+        write_children(w, std::slice::from_ref(self), false)
+    }
+
+    pub fn write_html_io<W: io::Write>(&self, w: W) -> io::Result<()> {
+        let mut writer = IoWriter { inner: w, error: None };
+        self.write_html(&mut writer).map_err(|_| {
+            writer
+                .error
+                .take()
+                .unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "formatter error"))
+        })
+    }
+}
+
+impl<A> View<A> {
+    pub fn to_html(&self) -> String {
+        self.to_node().to_html()
+    }
+
+    pub fn write_html<W: Write>(&self, w: &mut W) -> fmt::Result {
+        self.to_node().write_html(w)
+    }
+
+    pub fn write_html_io<W: io::Write>(&self, w: W) -> io::Result<()> {
+        self.to_node().write_html_io(w)
+    }
+}
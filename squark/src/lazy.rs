@@ -0,0 +1,341 @@
+use std::any::{Any, TypeId};
+use std::cell::Cell;
+use std::fmt::{self, Debug, Write};
+use std::rc::Rc;
+use rustc_hash::FxHashMap;
+
+use crate::vdom::{inherit_namespace, HandlerMap, Namespace, Node, View};
+
+thread_local! {
+    static LAZY_ID: Cell<usize> = Cell::new(0);
+}
+
+trait LazyArgs {
+    fn as_any(&self) -> &dyn Any;
+
+    fn eq_args(&self, other: &dyn LazyArgs) -> bool;
+}
+
+impl<T: PartialEq + 'static> LazyArgs for T {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn eq_args(&self, other: &dyn LazyArgs) -> bool {
+        other.as_any().downcast_ref::<T>() == Some(self)
+    }
+}
+
+pub(crate) type LazyMap<A> = FxHashMap<String, Rc<dyn Fn() -> View<A>>>;
+
+/// Subtree made by `View::lazy`, which is rendered again only when its arguments are changed
+#[derive(Clone)]
+pub struct Lazy {
+    id: String,
+    render_type: TypeId,
+    args: Rc<dyn LazyArgs>,
+    node: Option<Rc<Node>>,
+    path: String,
+    index: usize,
+    handler_ids: Rc<Vec<String>>,
+    /// Namespace inherited from parent, which is given to rendered subtree
+    pub(crate) namespace: Namespace,
+}
+
+impl Lazy {
+    /// Rendered subtree. It is `None` until runtime renders the view.
+    pub fn node(&self) -> Option<&Node> {
+        self.node.as_deref()
+    }
+
+    /// Whether both are the same subtree which is reused
+    pub(crate) fn shares_node(&self, other: &Lazy) -> bool {
+        match (&self.node, &other.node) {
+            (Some(a), Some(b)) => Rc::ptr_eq(a, b),
+            _ => false,
+        }
+    }
+
+    fn is_same(&self, other: &Lazy) -> bool {
+        self.render_type == other.render_type
+            && self.namespace == other.namespace
+            && self.args.eq_args(&*other.args)
+    }
+}
+
+impl Debug for Lazy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("Lazy").field(&self.node()).finish()
+    }
+}
+
+impl PartialEq for Lazy {
+    fn eq(&self, other: &Lazy) -> bool {
+        self.node() == other.node()
+    }
+}
+
+impl<A: 'static> View<A> {
+    /// Subtree rendered by `render` from `args`.
+    ///
+    /// While `args` is equal to the one of previous render at the same position, runtime reuses
+    /// previous subtree and its handlers, and skips both of calling `render` and diffing the subtree.
+    /// So `render` should depend on nothing but `args`.
+    pub fn lazy<T, F>(args: T, render: F) -> View<A>
+    where
+        T: PartialEq + 'static,
+        F: Fn(&T) -> View<A> + 'static,
+    {
+        let id = LAZY_ID.with(|id| {
+            id.set(id.get().wrapping_add(1));
+            id.get().to_string()
+        });
+        let args = Rc::new(args);
+        let render_args = args.clone();
+
+        let mut lazy_map: LazyMap<A> = FxHashMap::default();
+        lazy_map.insert(id.to_owned(), Rc::new(move || render(&render_args)));
+        View {
+            node: Node::Lazy(Lazy {
+                id,
+                render_type: TypeId::of::<F>(),
+                args,
+                node: None,
+                path: String::new(),
+                index: 0,
+                handler_ids: Rc::new(vec![]),
+                namespace: Namespace::Html,
+            }),
+            handler_map: FxHashMap::default(),
+            lazy_map,
+        }
+    }
+}
+
+/// Render all lazy subtrees without caring handlers, such as to render HTML
+pub(crate) fn fill_lazies<A>(node: &mut Node, lazy_map: &LazyMap<A>) {
+    match node {
+        Node::Element(el) => {
+            for child in el.children.iter_mut() {
+                fill_lazies(child, lazy_map);
+            }
+        }
+        Node::Fragment(children) => {
+            for child in children.iter_mut() {
+                fill_lazies(child, lazy_map);
+            }
+        }
+        Node::Lazy(lazy) if lazy.node.is_none() => {
+            if let Some(render) = lazy_map.get(&lazy.id) {
+                let view = render();
+                let mut rendered = view.node;
+                inherit_namespace(&mut rendered, lazy.namespace);
+                fill_lazies(&mut rendered, &view.lazy_map);
+                lazy.node = Some(Rc::new(rendered));
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Segments of path for children of an element or fragment.
+/// Key is prefixed by its length so that it can contain any character,
+/// and repeated key among siblings is followed by its occurrence.
+#[derive(Default)]
+struct Segments(FxHashMap<String, usize>);
+
+impl Segments {
+    fn push(&mut self, path: &mut String, i: usize, node: &Node) {
+        let key = match node.get_key() {
+            Some(key) => key,
+            None => return write!(path, ">{}", i).unwrap(),
+        };
+        write!(path, ">#{}:{}", key.len(), key).unwrap();
+        let occurrence = self.0.entry(key).or_insert(0);
+        if *occurrence > 0 {
+            write!(path, "#{}", occurrence).unwrap();
+        }
+        *occurrence += 1;
+    }
+}
+
+/// Lazy children of previous tree for each path of parent
+fn collect_lazies(children: &[Node], path: &mut String, out: &mut FxHashMap<String, Vec<Lazy>>) {
+    let mut segments = Segments::default();
+    for (i, child) in children.iter().enumerate() {
+        let len = path.len();
+        segments.push(path, i, child);
+        match child {
+            Node::Lazy(lazy) => out.entry(path[..len].to_owned()).or_default().push(lazy.to_owned()),
+            Node::Element(el) => collect_lazies(&el.children, path, out),
+            Node::Fragment(fragment) => collect_lazies(fragment, path, out),
+            _ => (),
+        }
+        path.truncate(len);
+    }
+}
+
+enum Resolved {
+    Reused,
+    /// Newly rendered, with previous lazy at the same position whose descendants can be reused
+    Rendered(Option<Lazy>),
+}
+
+/// Render lazy nodes of new view or reuse ones of previous tree,
+/// and give handlers ids derived from position of element.
+pub(crate) struct Resolver<A> {
+    old_lazies: FxHashMap<String, Vec<Lazy>>,
+    old_handler_map: HandlerMap<A>,
+    temporary: HandlerMap<A>,
+    lazy_map: LazyMap<A>,
+    handler_map: HandlerMap<A>,
+    /// Handler ids in each lazy subtree which is being resolved
+    frames: Vec<Vec<String>>,
+}
+
+impl<A> Resolver<A> {
+    pub(crate) fn new(old_node: &Node, old_handler_map: HandlerMap<A>) -> Resolver<A> {
+        let mut old_lazies = FxHashMap::default();
+        collect_lazies(std::slice::from_ref(old_node), &mut String::new(), &mut old_lazies);
+        Resolver {
+            old_lazies,
+            old_handler_map,
+            temporary: FxHashMap::default(),
+            lazy_map: FxHashMap::default(),
+            handler_map: FxHashMap::default(),
+            frames: vec![],
+        }
+    }
+
+    pub(crate) fn resolve(mut self, view: &mut View<A>) {
+        self.temporary = std::mem::take(&mut view.handler_map);
+        self.lazy_map = std::mem::take(&mut view.lazy_map);
+        self.resolve_children(std::slice::from_mut(&mut view.node), &mut String::new(), false);
+        view.handler_map = self.handler_map;
+    }
+
+    fn resolve_children(&mut self, children: &mut [Node], path: &mut String, from_old: bool) {
+        let mut segments = Segments::default();
+        for (i, child) in children.iter_mut().enumerate() {
+            let resolved = match child {
+                Node::Lazy(lazy) if lazy.node.is_none() => Some(self.render_lazy(lazy, path, i)),
+                _ => None,
+            };
+
+            let len = path.len();
+            segments.push(path, i, child);
+            match (child, resolved) {
+                (Node::Lazy(lazy), Some(Resolved::Rendered(prev))) => {
+                    if let Some(node) = prev.as_ref().and_then(|prev| prev.node()) {
+                        match node {
+                            Node::Element(el) => collect_lazies(&el.children, path, &mut self.old_lazies),
+                            Node::Fragment(fragment) => collect_lazies(fragment, path, &mut self.old_lazies),
+                            _ => (),
+                        }
+                    }
+                    self.resolve_lazy(lazy, path, false);
+                }
+                (child, _) => self.resolve_node(child, path, from_old),
+            }
+            path.truncate(len);
+        }
+    }
+
+    fn resolve_node(&mut self, node: &mut Node, path: &mut String, from_old: bool) {
+        match node {
+            Node::Element(el) => {
+                for &mut (ref name, ref mut id) in el.handlers.iter_mut() {
+                    let new_id = format!("{}/{}", path, name);
+                    let f = if from_old {
+                        self.old_handler_map.get(id).cloned()
+                    } else {
+                        self.temporary.remove(id)
+                    };
+                    if let Some(f) = f {
+                        self.handler_map.insert(new_id.to_owned(), f);
+                    }
+                    if let Some(frame) = self.frames.last_mut() {
+                        frame.push(new_id.to_owned());
+                    }
+                    *id = new_id;
+                }
+                self.resolve_children(&mut el.children, path, from_old);
+            }
+            Node::Fragment(children) => self.resolve_children(children, path, from_old),
+            Node::Lazy(lazy) => {
+                if lazy.node.is_none() {
+                    self.render(lazy);
+                    self.resolve_lazy(lazy, path, false);
+                } else if lazy.path == *path {
+                    self.reuse_handlers(lazy);
+                } else {
+                    self.resolve_lazy(lazy, path, true);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    fn render_lazy(&mut self, lazy: &mut Lazy, parent_path: &str, i: usize) -> Resolved {
+        lazy.index = i;
+        let mut prev = None;
+        if let Some(candidates) = self.old_lazies.get_mut(parent_path) {
+            let pos = candidates
+                .iter()
+                .position(|old| old.index == i && old.is_same(lazy))
+                .or_else(|| candidates.iter().position(|old| old.is_same(lazy)));
+            if let Some(pos) = pos {
+                let old = candidates.swap_remove(pos);
+                self.lazy_map.remove(&lazy.id);
+                lazy.node = old.node;
+                lazy.path = old.path;
+                lazy.handler_ids = old.handler_ids;
+                return Resolved::Reused;
+            }
+            prev = candidates
+                .iter()
+                .find(|old| old.index == i && old.render_type == lazy.render_type)
+                .cloned();
+        }
+        self.render(lazy);
+        Resolved::Rendered(prev)
+    }
+
+    fn render(&mut self, lazy: &mut Lazy) {
+        if let Some(render) = self.lazy_map.remove(&lazy.id) {
+            let view = render();
+            self.temporary.extend(view.handler_map);
+            self.lazy_map.extend(view.lazy_map);
+            let mut node = view.node;
+            inherit_namespace(&mut node, lazy.namespace);
+            lazy.node = Some(Rc::new(node));
+        }
+    }
+
+    fn reuse_handlers(&mut self, lazy: &Lazy) {
+        for id in lazy.handler_ids.iter() {
+            if let Some(f) = self.old_handler_map.get(id) {
+                self.handler_map.insert(id.to_owned(), f.clone());
+            }
+        }
+        if let Some(frame) = self.frames.last_mut() {
+            frame.extend(lazy.handler_ids.iter().cloned());
+        }
+    }
+
+    fn resolve_lazy(&mut self, lazy: &mut Lazy, path: &mut String, from_old: bool) {
+        let mut node = match lazy.node.take() {
+            Some(node) => Rc::try_unwrap(node).unwrap_or_else(|node| (*node).to_owned()),
+            None => return,
+        };
+        self.frames.push(vec![]);
+        self.resolve_node(&mut node, path, from_old);
+        let handler_ids = self.frames.pop().unwrap();
+        if let Some(frame) = self.frames.last_mut() {
+            frame.extend(handler_ids.iter().cloned());
+        }
+        lazy.node = Some(Rc::new(node));
+        lazy.path = path.to_owned();
+        lazy.handler_ids = Rc::new(handler_ids);
+    }
+}
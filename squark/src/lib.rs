@@ -3,16 +3,39 @@ use std::cell::{Cell, RefCell};
 use std::fmt::Debug;
 use rustc_hash::FxHashMap;
 use std::rc::Rc;
-use futures::Future;
-use serde::Serialize;
+use futures::future::{self, FutureExt, LocalBoxFuture};
+use futures::stream::StreamExt;
 
 mod vdom;
-
-pub use crate::vdom::{Node, Element, Diff, View, HandlerArg, AttributeValue, Child};
+mod sub;
+mod test_runtime;
+mod html;
+mod history;
+mod middleware;
+mod task;
+mod lazy;
+mod node_ref;
+
+pub use crate::vdom::{
+    Node, Element, Diff, View, HandlerArg, AttributeValue, Child, Namespace, property_name,
+    is_boolean_attribute, LIFECYCLE_HOOKS, ListenerOptions, listener_options,
+};
+pub use crate::sub::{Sub, Subscriber, Unsubscribe};
+pub use crate::test_runtime::{TestRuntime, TestNode, TestElement};
+pub use crate::html::{ESCAPABLE_RAW_TEXT_ELEMENTS, HYDRATION_MARKER};
+pub use crate::history::TimeTravel;
+pub use crate::middleware::Middleware;
+pub use crate::task::{Task, TaskError};
+pub use crate::lazy::Lazy;
+pub use crate::node_ref::Ref;
+use crate::task::{next_task_id, Command, ErrorHandler, RunningMap, Step};
+use crate::history::History;
 use crate::vdom::{HandlerFunction, HandlerMap};
+use crate::sub::{Active, ActiveMap};
 
 thread_local! {
     static RNG: RefCell<SmallRng> = RefCell::new(SmallRng::from_entropy());
+    static HANDLER_ID: Cell<usize> = Cell::new(0);
 }
 
 pub trait App: 'static + Clone + Default {
@@ -22,13 +45,22 @@ pub trait App: 'static + Clone + Default {
     fn reducer(&self, state: Self::State, action: Self::Action) -> (Self::State, Task<Self::Action>);
 
     fn view(&self, state: Self::State) -> View<Self::Action>;
+
+    fn subscriptions(&self, _state: &Self::State) -> Sub<Self::Action> {
+        Sub::empty()
+    }
 }
 
+/// Id given here is temporary. Runtime replaces it with one derived from position of element on rendering.
 pub fn handler<A, F>(f: F) -> (String, HandlerFunction<A>)
 where
     F: Fn(HandlerArg) -> Option<A> + 'static,
 {
-    (uuid(), Box::new(f))
+    let id = HANDLER_ID.with(|id| {
+        id.set(id.get().wrapping_add(1));
+        id.get()
+    });
+    (id.to_string(), Box::new(f))
 }
 
 #[derive(Clone)]
@@ -38,6 +70,14 @@ pub struct Env<A: App> {
     node: Rc<RefCell<Node>>,
     handler_map: Rc<RefCell<HandlerMap<A::Action>>>,
     scheduled: Rc<Cell<bool>>,
+    subscriptions: Rc<RefCell<ActiveMap<A::Action>>>,
+    history: Rc<RefCell<Option<History<A>>>>,
+    middlewares: Rc<RefCell<Vec<Rc<dyn Middleware<A>>>>>,
+    running: Rc<RefCell<RunningMap>>,
+    error_handler: Rc<RefCell<Option<ErrorHandler<A::Action>>>>,
+    after_render: Rc<RefCell<Vec<Box<dyn FnOnce()>>>>,
+    /// Handlers of previous view while diffs are applied, to call `unmount` hook of removed element
+    previous_handler_map: Rc<RefCell<HandlerMap<A::Action>>>,
 }
 
 impl<A: App> Env<A> {
@@ -48,6 +88,13 @@ impl<A: App> Env<A> {
             node: Rc::new(RefCell::new(Node::Null)),
             handler_map: Rc::new(RefCell::new(FxHashMap::default())),
             scheduled: Rc::new(Cell::new(false)),
+            subscriptions: Rc::new(RefCell::new(FxHashMap::default())),
+            history: Rc::new(RefCell::new(None)),
+            middlewares: Rc::new(RefCell::new(vec![])),
+            running: Rc::new(RefCell::new(FxHashMap::default())),
+            error_handler: Rc::new(RefCell::new(None)),
+            after_render: Rc::new(RefCell::new(vec![])),
+            previous_handler_map: Rc::new(RefCell::new(FxHashMap::default())),
         }
     }
 
@@ -67,30 +114,9 @@ impl<A: App> Env<A> {
         *self.node.borrow_mut() = node;
     }
 
-    fn pop_handler(&self, id: &str) -> Option<HandlerFunction<A::Action>> {
-        self.handler_map.borrow_mut().remove(id)
-    }
-}
-
-pub struct Task<A>(Vec<Box<Future<Item = A, Error = ()>>>);
-
-impl<A> Default for Task<A> {
-    fn default() -> Self {
-        Task(vec![])
-    }
-}
-
-impl<A> Task<A> {
-    pub fn empty() -> Self {
-        Self::default()
-    }
-
-    pub fn into_futures(self) -> Vec<Box<Future<Item = A, Error = ()>>> {
-        self.0
-    }
-
-    pub fn push(&mut self, future: Box<Future<Item = A, Error = ()>>) {
-        self.0.push(future);
+    fn call_handler(&self, id: &str, arg: HandlerArg) -> Option<A::Action> {
+        let handler_map = self.handler_map.borrow();
+        handler_map.get(id).and_then(|handler| handler(arg))
     }
 }
 
@@ -99,7 +125,7 @@ pub trait Runtime<A: App>: Clone + 'static {
 
     fn handle_diff(&self, diff: Diff);
 
-    fn handle_future<T: Serialize + 'static, E: Serialize + 'static>(&self, future: Box<Future<Item = T, Error = E>>);
+    fn handle_future(&self, future: LocalBoxFuture<'static, ()>);
 
     fn schedule_render(&self);
 
@@ -108,30 +134,109 @@ pub trait Runtime<A: App>: Clone + 'static {
     }
 
     fn run_with_task(&self, task: Task<A::Action>) {
-        for future in task.into_futures() {
-            self.emit_future(future);
-        }
+        self.sync_subscriptions();
+        self.emit_task(task);
 
         let env = self.get_env();
         env.scheduled.set(false);
         let mut old_node = env.get_node();
-        let view = env.app.view(env.get_state());
+        let mut view = env.app.view(env.get_state());
+        let old_handler_map = env.handler_map.replace(FxHashMap::default());
+        view.resolve(&old_node, old_handler_map.to_owned());
         *env.handler_map.borrow_mut() = view.handler_map;
-        if let Some(diff) = Node::diff(&mut old_node, &view.node, &mut 0) {
+        *env.previous_handler_map.borrow_mut() = old_handler_map;
+        let diffs = Node::diff(&mut old_node, &view.node);
+        if !diffs.is_empty() {
             env.set_node(view.node);
+        }
+        for diff in diffs {
             self.handle_diff(diff);
         }
+        env.previous_handler_map.borrow_mut().clear();
+        self.flush_after_render();
+    }
+
+    /// Call `f` after diffs of current or next render are applied, such as to call lifecycle hooks
+    fn after_render<F: FnOnce() + 'static>(&self, f: F) {
+        self.get_env().after_render.borrow_mut().push(Box::new(f));
+    }
+
+    /// Call lifecycle hook `name` with handler `id` after render. See `LIFECYCLE_HOOKS`.
+    fn call_hook(&self, name: &str, id: &str) {
+        let env = self.get_env();
+        // Removed element is not in current view, so its `unmount` hook is one of previous view
+        let handler_map = match name {
+            "unmount" => env.previous_handler_map.borrow(),
+            _ => env.handler_map.borrow(),
+        };
+        let handler = match handler_map.get(id) {
+            Some(handler) => handler.to_owned(),
+            None => return,
+        };
+        let this = self.to_owned();
+        self.after_render(move || {
+            if let Some(a) = handler(HandlerArg::Null) {
+                this.on_action(a);
+            }
+        });
+    }
+
+    /// Call functions given by `after_render`. Runtime calls this after applying diffs.
+    fn flush_after_render(&self) {
+        let after_render = self.get_env().after_render.replace(vec![]);
+        for f in after_render {
+            f();
+        }
+    }
+
+    /// Build view of current state and regard it as already rendered.
+    /// Runtime uses returned node to take over existing tree such as server-rendered markup.
+    fn commit_view(&self) -> Node {
+        self.sync_subscriptions();
+        let env = self.get_env();
+        env.scheduled.set(false);
+        let mut view = env.app.view(env.get_state());
+        view.resolve(&env.get_node(), env.handler_map.replace(FxHashMap::default()));
+        *env.handler_map.borrow_mut() = view.handler_map;
+        env.set_node(view.node.to_owned());
+        view.node
     }
 
     fn on_action(&self, action: A::Action) {
         let env = self.get_env();
+        if env.hold_if_paused(&action) {
+            return;
+        }
 
-        let old_state = env.get_state();
-        let (new_state, task) = env.app.reducer(old_state.to_owned(), action);
-        for future in task.into_futures() {
-            self.emit_future(future);
+        let mut task = Task::empty();
+        let middlewares = env.get_middlewares();
+        let action = middlewares
+            .iter()
+            .try_fold(action, |a, m| m.before(a, &mut task));
+
+        let new_state = action.map(|action| {
+            let old_state = env.get_state();
+            let (new_state, reducer_task) = env.app.reducer(old_state.to_owned(), action.to_owned());
+            task.append(reducer_task);
+            for m in middlewares.iter().rev() {
+                m.after(&action, &old_state, &new_state, &mut task);
+            }
+            env.record(action, &new_state);
+            new_state
+        });
+
+        self.emit_task(task);
+        if let Some(new_state) = new_state {
+            self.set_state(new_state);
+        }
+        // Render even if state is unchanged, so that functions given by `Task::after_render` are called
+        if !env.after_render.borrow().is_empty() {
+            self.request_render();
         }
-        self.set_state(new_state);
+    }
+
+    fn add_middleware<M: Middleware<A> + 'static>(&self, middleware: M) {
+        self.get_env().add_middleware(Rc::new(middleware));
     }
 
     fn set_state(&self, new_state: A::State) {
@@ -141,6 +246,12 @@ pub trait Runtime<A: App>: Clone + 'static {
             return;
         }
         env.set_state(new_state);
+        self.request_render();
+    }
+
+    /// Schedule render unless it is already scheduled
+    fn request_render(&self) {
+        let env = self.get_env();
         if env.scheduled.get() {
             return;
         }
@@ -148,24 +259,128 @@ pub trait Runtime<A: App>: Clone + 'static {
         self.schedule_render();
     }
 
-    fn emit_future(&self, task: Box<Future<Item = A::Action, Error = ()>>) {
-        let this = self.clone();
-        self.handle_future(Box::new(task.map(move |a| {
-            this.on_action(a);
-        })));
+    /// Start or stop subscriptions for current state.
+    /// It is called when view is built, which follows every state change.
+    fn sync_subscriptions(&self) {
+        let env = self.get_env();
+        let sub = env.app.subscriptions(&env.get_state());
+        let mut old_map = env.subscriptions.replace(FxHashMap::default());
+        let mut new_map = FxHashMap::default();
+        let mut occurrences = FxHashMap::<String, usize>::default();
+        for (key, subscriber, f) in sub.into_subscriptions() {
+            // Same source can be subscribed from different parts of app, such as two `interval(1000, ..)`,
+            // so repeated key is told apart by its occurrence
+            let occurrence = occurrences.entry(key.clone()).or_insert(0);
+            let key = (key, *occurrence);
+            *occurrence += 1;
+            if let Some(active) = old_map.remove(&key) {
+                *active.handler.borrow_mut() = f;
+                new_map.insert(key, active);
+                continue;
+            }
+
+            let handler = Rc::new(RefCell::new(f));
+            let this = self.to_owned();
+            let h = handler.clone();
+            let unsubscribe = subscriber(Box::new(move |arg| {
+                let action = (h.borrow())(arg);
+                if let Some(a) = action {
+                    this.on_action(a);
+                }
+            }));
+            new_map.insert(key, Active { handler, unsubscribe });
+        }
+        *env.subscriptions.borrow_mut() = new_map;
+
+        for (_, active) in old_map.drain() {
+            (active.unsubscribe)();
+        }
     }
 
-    fn pop_handler(&self, id: &str) -> Option<Box<Fn(HandlerArg)>> {
+    fn emit_task(&self, task: Task<A::Action>) {
         let env = self.get_env();
-        let handler = env.pop_handler(id)?;
-        let this = self.to_owned();
-        let f = move |arg: HandlerArg| {
-            match handler(arg) {
-                Some(a) => this.on_action(a),
-                None => return,
+        for command in task.into_commands() {
+            let (key, stream) = match command {
+                Command::Run(key, stream) => (key, stream),
+                Command::Cancel(key) => {
+                    env.cancel_task(&key);
+                    continue;
+                }
+                Command::AfterRender(f) => {
+                    self.after_render(f);
+                    continue;
+                }
+            };
+
+            let this = self.to_owned();
+            let future = stream.for_each(move |result| {
+                match result {
+                    Ok(Step::Action(a)) => this.on_action(a),
+                    Ok(Step::Cancel(key)) => this.get_env().cancel_task(&key),
+                    Ok(Step::AfterRender(f)) => {
+                        this.after_render(f);
+                        this.request_render();
+                    }
+                    Ok(Step::Start(key, id, handle)) => {
+                        this.get_env().start_keyed_task(&key, id, handle)
+                    }
+                    Ok(Step::Finish(key, id)) => this.get_env().finish_keyed_task(&key, id),
+                    Err(e) => this.handle_error(e),
+                };
+                future::ready(())
+            });
+            let key = match key {
+                Some(key) => key,
+                None => {
+                    self.handle_future(future.boxed_local());
+                    continue;
+                }
             };
+
+            let (future, handle) = future::abortable(future);
+            let id = next_task_id();
+            env.start_keyed_task(&key, id, handle);
+            let env = env.to_owned();
+            self.handle_future(
+                future
+                    .map(move |_| env.finish_keyed_task(&key, id))
+                    .boxed_local(),
+            );
+        }
+    }
+
+    /// Map error of task which is not mapped by the task itself into action.
+    fn set_error_handler<F>(&self, f: F)
+    where
+        F: Fn(TaskError) -> Option<A::Action> + 'static,
+    {
+        self.get_env().set_error_handler(Rc::new(f));
+    }
+
+    /// Report error when no error handler is set.
+    fn report_error(&self, _error: TaskError) {}
+
+    fn handle_error(&self, error: TaskError) {
+        let handler = match self.get_env().get_error_handler() {
+            Some(handler) => handler,
+            None => return self.report_error(error),
         };
-        Some(Box::new(f))
+        if let Some(a) = handler(error) {
+            self.on_action(a);
+        }
+    }
+
+    /// Listener to be attached for handler `id`.
+    /// It calls the handler of latest view, so the listener can be kept while the id is unchanged.
+    fn listener(&self, id: &str) -> Box<dyn Fn(HandlerArg)> {
+        let this = self.to_owned();
+        let id = id.to_owned();
+        Box::new(move |arg: HandlerArg| {
+            let action = this.get_env().call_handler(&id, arg);
+            if let Some(a) = action {
+                this.on_action(a);
+            }
+        })
     }
 }
 
@@ -0,0 +1,33 @@
+use std::rc::Rc;
+
+use crate::{App, Env, Task};
+
+/// Hook around reducer, registered by `Runtime::add_middleware`.
+///
+/// `before` of middlewares are called in registered order, and `after` are called in reverse order.
+pub trait Middleware<A: App> {
+    /// Called before reducer. Returning `None` drops the action.
+    fn before(&self, action: A::Action, _task: &mut Task<A::Action>) -> Option<A::Action> {
+        Some(action)
+    }
+
+    /// Called after reducer with states before and after the action.
+    fn after(
+        &self,
+        _action: &A::Action,
+        _old_state: &A::State,
+        _new_state: &A::State,
+        _task: &mut Task<A::Action>,
+    ) {
+    }
+}
+
+impl<A: App> Env<A> {
+    pub(crate) fn get_middlewares(&self) -> Vec<Rc<dyn Middleware<A>>> {
+        self.middlewares.borrow().to_owned()
+    }
+
+    pub(crate) fn add_middleware(&self, middleware: Rc<dyn Middleware<A>>) {
+        self.middlewares.borrow_mut().push(middleware);
+    }
+}
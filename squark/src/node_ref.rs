@@ -0,0 +1,45 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+use std::rc::Rc;
+
+/// Handle to rendered element, which is given as attribute value such as `ref={ r.clone() }`.
+/// Runtime sets the element while it is mounted, so that `Task` can access it.
+/// `WebRuntime` of `squark-web` sets `web_sys::Element`, and `TestRuntime` sets `TestElement`.
+#[derive(Clone, Default)]
+pub struct Ref(Rc<RefCell<Option<Box<dyn Any>>>>);
+
+impl Ref {
+    pub fn new() -> Ref {
+        Ref::default()
+    }
+
+    /// Element set by runtime, or `None` if it is not mounted or not of type `T`
+    pub fn get<T: Clone + 'static>(&self) -> Option<T> {
+        self.0
+            .borrow()
+            .as_ref()
+            .and_then(|el| el.downcast_ref::<T>())
+            .cloned()
+    }
+
+    pub fn set<T: 'static>(&self, el: T) {
+        *self.0.borrow_mut() = Some(Box::new(el));
+    }
+
+    pub fn clear(&self) {
+        *self.0.borrow_mut() = None;
+    }
+}
+
+impl Debug for Ref {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("Ref")
+    }
+}
+
+impl PartialEq for Ref {
+    fn eq(&self, other: &Ref) -> bool {
+        Rc::ptr_eq(&self.0, &other.0)
+    }
+}
@@ -0,0 +1,74 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use rustc_hash::FxHashMap;
+
+use crate::vdom::{HandlerArg, HandlerFunction};
+
+pub type Unsubscribe = Box<dyn FnOnce()>;
+pub type Subscriber = Box<dyn FnOnce(Box<dyn Fn(HandlerArg)>) -> Unsubscribe>;
+
+/// Long-lived event sources returned from `App::subscriptions`.
+///
+/// Each subscription is identified by its key. Runtime starts a subscription when its key appears,
+/// and stops it when the key disappears. While the key is kept, only handler is swapped.
+/// Subscriptions with same key are all kept, and told apart by their order.
+pub struct Sub<A>(Vec<(String, Subscriber, HandlerFunction<A>)>);
+
+impl<A> Default for Sub<A> {
+    fn default() -> Self {
+        Sub(vec![])
+    }
+}
+
+impl<A> Sub<A> {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// `subscriber` is called with a callback to emit events, and returns function to stop emitting.
+    pub fn new<S, F>(key: String, subscriber: S, f: F) -> Self
+    where
+        S: FnOnce(Box<dyn Fn(HandlerArg)>) -> Unsubscribe + 'static,
+        F: Fn(HandlerArg) -> Option<A> + 'static,
+    {
+        Sub(vec![(key, Box::new(subscriber), Box::new(f))])
+    }
+
+    pub fn batch(subs: Vec<Sub<A>>) -> Self {
+        Sub(subs.into_iter().flat_map(|s| s.0).collect())
+    }
+
+    pub fn push(&mut self, sub: Sub<A>) {
+        self.0.extend(sub.0);
+    }
+
+    /// Lift emitted actions into other action type, such as to embed subscriptions of child app into parent.
+    pub fn map<B, F>(self, f: F) -> Sub<B>
+    where
+        A: 'static,
+        F: Fn(A) -> B + 'static,
+    {
+        let f = Rc::new(f);
+        Sub(self
+            .0
+            .into_iter()
+            .map(|(key, subscriber, handler)| {
+                let f = f.clone();
+                let mapped: HandlerFunction<B> = Box::new(move |arg| handler(arg).map(&*f));
+                (key, subscriber, mapped)
+            })
+            .collect())
+    }
+
+    pub(crate) fn into_subscriptions(self) -> Vec<(String, Subscriber, HandlerFunction<A>)> {
+        self.0
+    }
+}
+
+pub(crate) struct Active<A> {
+    pub(crate) handler: Rc<RefCell<HandlerFunction<A>>>,
+    pub(crate) unsubscribe: Unsubscribe,
+}
+
+/// Active subscriptions by key and occurrence of the key
+pub(crate) type ActiveMap<A> = FxHashMap<(String, usize), Active<A>>;
@@ -0,0 +1,289 @@
+use std::any::Any;
+use std::cell::Cell;
+use std::fmt::{self, Debug};
+use std::future::Future;
+use std::rc::Rc;
+use futures::compat::Future01CompatExt;
+use futures::future::{self, AbortHandle, FutureExt};
+use futures::stream::{self, LocalBoxStream, StreamExt};
+use rustc_hash::FxHashMap;
+
+use crate::{App, Env};
+
+/// Error of task which is not mapped into action.
+/// It is passed to error handler of runtime, which is set by `Runtime::set_error_handler`.
+pub struct TaskError {
+    message: String,
+    error: Box<dyn Any>,
+}
+
+impl TaskError {
+    pub fn new<E: Debug + 'static>(error: E) -> TaskError {
+        TaskError {
+            message: format!("{:?}", error),
+            error: Box::new(error),
+        }
+    }
+
+    /// `Debug` representation of original error
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    pub fn downcast_ref<E: 'static>(&self) -> Option<&E> {
+        self.error.downcast_ref()
+    }
+}
+
+impl Debug for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "TaskError({})", self.message)
+    }
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+type TaskStream<A> = LocalBoxStream<'static, Result<Step<A>, TaskError>>;
+
+pub(crate) enum Command<A> {
+    Run(Option<String>, TaskStream<A>),
+    Cancel(String),
+    AfterRender(Box<dyn FnOnce()>),
+}
+
+/// Item yielded by stream of task. Commands other than `Run` are yielded in `Task::sequence`,
+/// so that they are run after previous tasks.
+/// `Start` and `Finish` wrap keyed task which is merged into other stream, so that it keeps its key.
+pub(crate) enum Step<A> {
+    Action(A),
+    Cancel(String),
+    AfterRender(Box<dyn FnOnce()>),
+    Start(String, usize, AbortHandle),
+    Finish(String, usize),
+}
+
+impl<A> Step<A> {
+    fn map<B, F: Fn(A) -> B>(self, f: F) -> Step<B> {
+        match self {
+            Step::Action(a) => Step::Action(f(a)),
+            Step::Cancel(key) => Step::Cancel(key),
+            Step::AfterRender(f) => Step::AfterRender(f),
+            Step::Start(key, id, handle) => Step::Start(key, id, handle),
+            Step::Finish(key, id) => Step::Finish(key, id),
+        }
+    }
+}
+
+/// Async work emitted from reducer. Each action it yields is dispatched to the app.
+pub struct Task<A>(Vec<Command<A>>);
+
+impl<A> Default for Task<A> {
+    fn default() -> Self {
+        Task(vec![])
+    }
+}
+
+impl<A: 'static> Task<A> {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    pub fn new<F>(future: F) -> Self
+    where
+        F: Future<Output = A> + 'static,
+    {
+        let mut task = Self::empty();
+        task.push(future);
+        task
+    }
+
+    /// Run `future` and map its result into action
+    pub fn perform<F, T, E, O, R>(future: F, on_ok: O, on_err: R) -> Self
+    where
+        F: Future<Output = Result<T, E>> + 'static,
+        O: FnOnce(T) -> A + 'static,
+        R: FnOnce(E) -> A + 'static,
+    {
+        Self::new(future.map(|result| match result {
+            Ok(v) => on_ok(v),
+            Err(e) => on_err(e),
+        }))
+    }
+
+    /// Run tasks concurrently
+    pub fn batch(tasks: Vec<Task<A>>) -> Self {
+        Task(tasks.into_iter().flat_map(|t| t.0).collect())
+    }
+
+    /// Run tasks one by one. Next task is started after all actions of previous one are emitted.
+    /// Cancellation and `Task::after_render` in a task are run when the task is started.
+    /// Keyed task keeps its key, so it can be cancelled while the sequence is running.
+    pub fn sequence(tasks: Vec<Task<A>>) -> Self {
+        let streams = tasks.into_iter().map(|task| {
+            let (stream, others) = task.split();
+            let steps = others.into_iter().map(|command| match command {
+                Command::Cancel(key) => Ok(Step::Cancel(key)),
+                Command::AfterRender(f) => Ok(Step::AfterRender(f)),
+                Command::Run(..) => unreachable!(),
+            });
+            let steps = stream::iter(steps.collect::<Vec<_>>());
+            match stream {
+                Some(stream) => steps.chain(stream).boxed_local(),
+                None => steps.boxed_local(),
+            }
+        });
+        let stream = stream::iter(streams.collect::<Vec<_>>()).flatten();
+        Task(vec![Command::Run(None, stream.boxed_local())])
+    }
+
+    /// Cancel running task which is started with `key`.
+    pub fn cancel(key: &str) -> Self {
+        Task(vec![Command::Cancel(key.to_owned())])
+    }
+
+    /// Call `f` after next render is committed, such as to focus element which is rendered by the render.
+    /// It emits no action.
+    pub fn after_render<F: FnOnce() + 'static>(f: F) -> Self {
+        Task(vec![Command::AfterRender(Box::new(f))])
+    }
+
+    /// Give `key` to this task, so that it can be cancelled by `Task::cancel`.
+    /// Starting keyed task cancels running one with same key, so only latest request survives.
+    /// Inner tasks keep their keys. Task without futures is left as it is.
+    pub fn with_key(self, key: &str) -> Self {
+        let (stream, mut commands) = self.split();
+        if let Some(stream) = stream {
+            commands.push(Command::Run(Some(key.to_owned()), stream));
+        }
+        Task(commands)
+    }
+
+    /// Lift emitted actions into other action type, such as to embed task of child app into parent.
+    pub fn map<B, F>(self, f: F) -> Task<B>
+    where
+        F: Fn(A) -> B + 'static,
+    {
+        let f = Rc::new(f);
+        Task(
+            self.0
+                .into_iter()
+                .map(|command| match command {
+                    Command::Run(key, stream) => {
+                        let f = f.clone();
+                        let stream = stream.map(move |r| r.map(|step| step.map(&*f)));
+                        Command::Run(key, stream.boxed_local())
+                    }
+                    Command::Cancel(key) => Command::Cancel(key),
+                    Command::AfterRender(f) => Command::AfterRender(f),
+                })
+                .collect(),
+        )
+    }
+
+    pub fn append(&mut self, other: Task<A>) {
+        self.0.extend(other.0);
+    }
+
+    pub fn push<F>(&mut self, future: F)
+    where
+        F: Future<Output = A> + 'static,
+    {
+        let stream = future.map(|a| Ok(Step::Action(a))).into_stream();
+        self.0.push(Command::Run(None, stream.boxed_local()));
+    }
+
+    /// Push fallible future. Its error is passed to error handler of runtime.
+    /// Use `Task::perform` to map error into action instead.
+    pub fn try_push<F, E>(&mut self, future: F)
+    where
+        F: Future<Output = Result<A, E>> + 'static,
+        E: Debug + 'static,
+    {
+        let stream = future.map(|r| r.map(Step::Action).map_err(TaskError::new)).into_stream();
+        self.0.push(Command::Run(None, stream.boxed_local()));
+    }
+
+    /// Push futures-0.1 future. Its error is passed to error handler of runtime.
+    pub fn push_compat<F>(&mut self, future: F)
+    where
+        F: futures01::Future<Item = A> + 'static,
+        F::Error: Debug + 'static,
+    {
+        self.try_push(future.compat());
+    }
+
+    pub(crate) fn into_commands(self) -> Vec<Command<A>> {
+        self.0
+    }
+
+    /// Merge all streams into one, if any, and take out other commands
+    fn split(self) -> (Option<TaskStream<A>>, Vec<Command<A>>) {
+        let mut streams = vec![];
+        let mut others = vec![];
+        for command in self.0 {
+            match command {
+                Command::Run(None, stream) => streams.push(stream),
+                Command::Run(Some(key), stream) => {
+                    let (stream, handle) = stream::abortable(stream);
+                    let id = next_task_id();
+                    let start = stream::once(future::ready(Ok(Step::Start(key.clone(), id, handle))));
+                    let finish = stream::once(future::ready(Ok(Step::Finish(key, id))));
+                    streams.push(start.chain(stream).chain(finish).boxed_local());
+                }
+                other => others.push(other),
+            }
+        }
+        if streams.is_empty() {
+            return (None, others);
+        }
+        (Some(stream::select_all(streams).boxed_local()), others)
+    }
+}
+
+pub(crate) type RunningMap = FxHashMap<String, (usize, AbortHandle)>;
+pub(crate) type ErrorHandler<A> = Rc<dyn Fn(TaskError) -> Option<A>>;
+
+thread_local! {
+    static TASK_ID: Cell<usize> = Cell::new(0);
+}
+
+pub(crate) fn next_task_id() -> usize {
+    TASK_ID.with(|id| {
+        id.set(id.get() + 1);
+        id.get()
+    })
+}
+
+impl<A: App> Env<A> {
+    /// Register abort handle of keyed task `id`, and abort previous one with the same key
+    pub(crate) fn start_keyed_task(&self, key: &str, id: usize, handle: AbortHandle) {
+        if let Some((_, old)) = self.running.borrow_mut().insert(key.to_owned(), (id, handle)) {
+            old.abort();
+        }
+    }
+
+    pub(crate) fn finish_keyed_task(&self, key: &str, id: usize) {
+        let mut running = self.running.borrow_mut();
+        if running.get(key).map_or(false, |&(running_id, _)| running_id == id) {
+            running.remove(key);
+        }
+    }
+
+    pub(crate) fn get_error_handler(&self) -> Option<ErrorHandler<A::Action>> {
+        self.error_handler.borrow().to_owned()
+    }
+
+    pub(crate) fn set_error_handler(&self, handler: ErrorHandler<A::Action>) {
+        *self.error_handler.borrow_mut() = Some(handler);
+    }
+
+    pub(crate) fn cancel_task(&self, key: &str) {
+        if let Some((_, handle)) = self.running.borrow_mut().remove(key) {
+            handle.abort();
+        }
+    }
+}
@@ -0,0 +1,480 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use futures::executor::{LocalPool, LocalSpawner};
+use futures::future::LocalBoxFuture;
+use futures::task::LocalSpawnExt;
+
+use crate::vdom::{apply_attribute, listener_options, AttributeValue, Diff, HandlerArg, Namespace, Node};
+use crate::{App, Env, Ref, Runtime, TaskError};
+
+/// Node of in-memory DOM maintained by `TestRuntime`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TestNode {
+    Text(String),
+    Element(TestElement),
+    /// Markup set as `innerHTML`, which is not parsed
+    RawHtml(String),
+}
+
+impl TestNode {
+    pub fn text(&self) -> String {
+        match self {
+            TestNode::Text(s) => s.to_owned(),
+            TestNode::Element(el) => el.text(),
+            TestNode::RawHtml(_) => String::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct TestElement {
+    name: String,
+    namespace: Namespace,
+    attributes: Vec<(String, AttributeValue)>,
+    handlers: Vec<(String, String)>,
+    children: Vec<TestNode>,
+}
+
+impl TestElement {
+    fn new(name: &str, namespace: Namespace) -> TestElement {
+        TestElement {
+            name: name.to_owned(),
+            namespace,
+            attributes: vec![],
+            handlers: vec![],
+            children: vec![],
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn namespace(&self) -> Namespace {
+        self.namespace
+    }
+
+    pub fn attributes(&self) -> &[(String, AttributeValue)] {
+        &self.attributes
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&AttributeValue> {
+        self.attributes
+            .iter()
+            .find(|&&(ref k, _)| k == name)
+            .map(|&(_, ref v)| v)
+    }
+
+    /// DOM property given as attribute with `prop:` prefix
+    pub fn property(&self, name: &str) -> Option<&AttributeValue> {
+        self.attribute(&format!("prop:{}", name))
+    }
+
+    /// Names of events which have listener
+    pub fn events(&self) -> Vec<&str> {
+        self.handlers.iter().map(|&(ref name, _)| name.as_str()).collect()
+    }
+
+    pub fn children(&self) -> &[TestNode] {
+        &self.children
+    }
+
+    /// Concatenated text of all descendant text nodes
+    pub fn text(&self) -> String {
+        self.children.iter().map(TestNode::text).collect()
+    }
+
+    /// Find first descendant matching `selector`.
+    ///
+    /// Supported selector is a subset of CSS: tag name, `#id`, `.class`, `[attr]`, `[attr=value]`,
+    /// and descendant combinator with whitespace.
+    pub fn find(&self, selector: &str) -> Option<&TestElement> {
+        self.find_all(selector).into_iter().next()
+    }
+
+    pub fn find_all(&self, selector: &str) -> Vec<&TestElement> {
+        let selector = parse_selector(selector);
+        let mut result = vec![];
+        collect_matched(self, &selector, &mut vec![], &mut result);
+        result
+    }
+
+    fn get_child_mut(&mut self, i: usize) -> &mut TestElement {
+        match self.children.get_mut(i) {
+            Some(TestNode::Element(el)) => el,
+            _ => panic!("child {} of <{}> is not an element", i, self.name),
+        }
+    }
+
+    /// Remove handler `id` from this element or its descendants
+    fn remove_handler(&mut self, id: &str) {
+        self.handlers.retain(|(_, i)| i != id);
+        for child in &mut self.children {
+            if let TestNode::Element(el) = child {
+                el.remove_handler(id);
+            }
+        }
+    }
+
+    fn set_handler(&mut self, name: String, id: String) {
+        match self.handlers.iter_mut().find(|&&mut (ref n, _)| n == &name) {
+            Some(attached) => attached.1 = id,
+            None => self.handlers.push((name, id)),
+        }
+    }
+}
+
+fn create_node(node: Node) -> Option<TestNode> {
+    match node {
+        Node::Element(el) => {
+            let mut test_el = TestElement::new(el.name(), el.namespace());
+            test_el.attributes = el
+                .attributes()
+                .iter()
+                .filter(|(_, value)| value != &AttributeValue::None)
+                .cloned()
+                .collect();
+            for (name, id) in el.handlers() {
+                test_el.set_handler(name.to_owned(), id.to_owned());
+            }
+            match el.raw_html() {
+                Some(html) => test_el.children.push(TestNode::RawHtml(html.to_owned())),
+                None => {
+                    for child in Node::flatten(el.children()) {
+                        if let Some(child) = create_node(child.to_owned()) {
+                            test_el.children.push(child);
+                        }
+                    }
+                }
+            }
+            Some(TestNode::Element(test_el))
+        }
+        Node::Text(s) | Node::RawHtml(s) => Some(TestNode::Text(s)),
+        Node::Fragment(_) | Node::Lazy(_) | Node::Null => None,
+    }
+}
+
+/// Refs given to `el` and its descendants, with the elements they are given to
+fn collect_refs(el: &TestElement, refs: &mut Vec<(Ref, TestElement)>) {
+    for (_, value) in &el.attributes {
+        if let AttributeValue::Ref(r) = value {
+            refs.push((r.to_owned(), el.to_owned()));
+        }
+    }
+    for child in &el.children {
+        if let TestNode::Element(child) = child {
+            collect_refs(child, refs);
+        }
+    }
+}
+
+/// Ids of lifecycle hook `name` of `node` and its descendants, where children come before parent
+fn collect_hooks(node: &TestNode, name: &str, ids: &mut Vec<String>) {
+    if let TestNode::Element(el) = node {
+        for child in &el.children {
+            collect_hooks(child, name, ids);
+        }
+        ids.extend(el.handlers.iter().filter(|(n, _)| n == name).map(|(_, id)| id.to_owned()));
+    }
+}
+
+#[derive(Default)]
+struct Compound {
+    name: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+    attributes: Vec<(String, Option<String>)>,
+}
+
+impl Compound {
+    fn parse(s: &str) -> Compound {
+        let mut compound = Compound::default();
+        let mut rest = s;
+        let name_end = rest.find(&['#', '.', '['][..]).unwrap_or(rest.len());
+        if name_end > 0 && &rest[..name_end] != "*" {
+            compound.name = Some(rest[..name_end].to_owned());
+        }
+        rest = &rest[name_end..];
+
+        while !rest.is_empty() {
+            let (kind, body) = rest.split_at(1);
+            if kind == "[" {
+                let end = body.find(']').expect("unclosed attribute selector");
+                let attribute = &body[..end];
+                rest = &body[end + 1..];
+                match attribute.find('=') {
+                    Some(eq) => {
+                        let value = attribute[eq + 1..].trim_matches(|c| c == '"' || c == '\'');
+                        compound
+                            .attributes
+                            .push((attribute[..eq].to_owned(), Some(value.to_owned())));
+                    }
+                    None => compound.attributes.push((attribute.to_owned(), None)),
+                }
+                continue;
+            }
+
+            let end = body.find(&['#', '.', '['][..]).unwrap_or(body.len());
+            let value = body[..end].to_owned();
+            rest = &body[end..];
+            match kind {
+                "#" => compound.id = Some(value),
+                _ => compound.classes.push(value),
+            }
+        }
+        compound
+    }
+
+    fn matches(&self, el: &TestElement) -> bool {
+        let attribute_string = |name: &str| {
+            el.attribute(name).and_then(|value| value.to_attribute_string(name))
+        };
+
+        if let Some(ref name) = self.name {
+            if name != &el.name {
+                return false;
+            }
+        }
+
+        if let Some(ref id) = self.id {
+            if attribute_string("id").as_ref() != Some(id) {
+                return false;
+            }
+        }
+
+        if !self.classes.is_empty() {
+            let class = attribute_string("class").unwrap_or_default();
+            let class_list: Vec<&str> = class.split_whitespace().collect();
+            if !self.classes.iter().all(|c| class_list.contains(&c.as_str())) {
+                return false;
+            }
+        }
+
+        self.attributes.iter().all(|&(ref name, ref value)| {
+            match (attribute_string(name), value) {
+                (None, _) => false,
+                (Some(_), None) => true,
+                (Some(actual), Some(expected)) => &actual == expected,
+            }
+        })
+    }
+}
+
+fn parse_selector(selector: &str) -> Vec<Compound> {
+    selector.split_whitespace().map(Compound::parse).collect()
+}
+
+fn matches_selector(selector: &[Compound], el: &TestElement, ancestors: &[&TestElement]) -> bool {
+    let (last, rest) = match selector.split_last() {
+        Some(v) => v,
+        None => return false,
+    };
+    if !last.matches(el) {
+        return false;
+    }
+
+    let mut rest = rest.iter().rev().peekable();
+    for ancestor in ancestors.iter().rev() {
+        match rest.peek() {
+            Some(compound) if compound.matches(ancestor) => {
+                rest.next();
+            }
+            Some(_) => (),
+            None => break,
+        }
+    }
+    rest.peek().is_none()
+}
+
+fn collect_matched<'a>(
+    el: &'a TestElement,
+    selector: &[Compound],
+    ancestors: &mut Vec<&'a TestElement>,
+    result: &mut Vec<&'a TestElement>,
+) {
+    for child in &el.children {
+        if let TestNode::Element(child) = child {
+            if matches_selector(selector, child, ancestors) {
+                result.push(child);
+            }
+            ancestors.push(child);
+            collect_matched(child, selector, ancestors, result);
+            ancestors.pop();
+        }
+    }
+}
+
+/// Headless runtime which applies diffs to in-memory DOM.
+///
+/// Rendering is done synchronously when state is changed.
+/// Futures of `Task` are queued on local executor, and are not run until `run_until_stalled` is called.
+/// Task error which is not handled by error handler makes panic.
+/// `Ref` is set to snapshot of its element as `TestElement`, which is taken again on every diff.
+#[derive(Clone)]
+pub struct TestRuntime<A: App> {
+    env: Env<A>,
+    root: Rc<RefCell<TestElement>>,
+    pool: Rc<RefCell<LocalPool>>,
+    spawner: LocalSpawner,
+    refs: Rc<RefCell<Vec<Ref>>>,
+}
+
+impl<A: App> TestRuntime<A> {
+    pub fn new(state: A::State) -> TestRuntime<A> {
+        let pool = LocalPool::new();
+        let spawner = pool.spawner();
+        TestRuntime {
+            env: Env::new(state),
+            root: Rc::new(RefCell::new(TestElement::new("body", Namespace::Html))),
+            pool: Rc::new(RefCell::new(pool)),
+            spawner,
+            refs: Rc::new(RefCell::new(vec![])),
+        }
+    }
+
+    /// Snapshot of root element which the app is rendered into
+    pub fn root(&self) -> TestElement {
+        self.root.borrow().to_owned()
+    }
+
+    pub fn state(&self) -> A::State {
+        self.env.get_state()
+    }
+
+    pub fn find(&self, selector: &str) -> Option<TestElement> {
+        self.root.borrow().find(selector).cloned()
+    }
+
+    pub fn find_all(&self, selector: &str) -> Vec<TestElement> {
+        self.root.borrow().find_all(selector).into_iter().cloned().collect()
+    }
+
+    /// Fire event `name` on first element matching `selector`.
+    /// Listener with `once` option is removed after it is called.
+    ///
+    /// Panics if no element matches or the element has no listener for the event.
+    pub fn fire(&self, selector: &str, name: &str, arg: HandlerArg) {
+        let (id, once) = {
+            let root = self.root.borrow();
+            let el = root
+                .find(selector)
+                .unwrap_or_else(|| panic!("no element matches `{}`", selector));
+            el.handlers
+                .iter()
+                .map(|(n, id)| (listener_options(n), id))
+                .find(|((event, _), _)| *event == name)
+                .map(|((_, options), id)| (id.to_owned(), options.once))
+                .unwrap_or_else(|| panic!("`{}` has no listener for `{}`", selector, name))
+        };
+        if once {
+            self.root.borrow_mut().remove_handler(&id);
+        }
+        let listener = self.listener(&id);
+        listener(arg);
+    }
+
+    /// Run queued futures until all of them are completed or waiting for something else.
+    pub fn run_until_stalled(&self) {
+        self.pool.borrow_mut().run_until_stalled();
+    }
+
+    /// Call lifecycle hook `name` of `node` and its descendants after render
+    fn call_hooks(&self, node: &TestNode, name: &str) {
+        let mut ids = vec![];
+        collect_hooks(node, name, &mut ids);
+        for id in ids {
+            self.call_hook(name, &id);
+        }
+    }
+
+    /// Set snapshots of elements to their refs, and clear refs of removed elements
+    fn sync_refs(&self, root: &TestElement) {
+        let mut mounted = vec![];
+        collect_refs(root, &mut mounted);
+        let refs: Vec<Ref> = mounted.iter().map(|(r, _)| r.to_owned()).collect();
+        for r in self.refs.replace(vec![]) {
+            if !refs.contains(&r) {
+                r.clear();
+            }
+        }
+        for (r, el) in mounted {
+            r.set(el);
+        }
+        *self.refs.borrow_mut() = refs;
+    }
+
+    fn handle_diff_inner(&self, el: &mut TestElement, diff: Diff) {
+        if apply_attribute(&mut el.attributes, &diff) {
+            return;
+        }
+
+        match diff {
+            Diff::AddChild(i, node) => {
+                if let Some(child) = create_node(node) {
+                    self.call_hooks(&child, "mount");
+                    let i = i.min(el.children.len());
+                    el.children.insert(i, child);
+                }
+            }
+            Diff::PatchChild(i, diffs) => {
+                let child = el.get_child_mut(i);
+                for diff in diffs {
+                    self.handle_diff_inner(child, diff);
+                }
+                if let Some((_, id)) = child.handlers.iter().find(|(n, _)| n == "update") {
+                    self.call_hook("update", id);
+                }
+            }
+            Diff::ReplaceChild(i, node) => {
+                if let Some(child) = create_node(node) {
+                    self.call_hooks(&el.children[i], "unmount");
+                    self.call_hooks(&child, "mount");
+                    el.children[i] = child;
+                }
+            }
+            Diff::RemoveChild(i) => {
+                let child = el.children.remove(i);
+                self.call_hooks(&child, "unmount");
+            }
+            Diff::MoveChild(from, to) => {
+                let child = el.children.remove(from);
+                el.children.insert(to, child);
+            }
+            Diff::SetHandler(name, id) => el.set_handler(name, id),
+            Diff::RemoveHandler(name, _) => el.handlers.retain(|&(ref n, _)| n != &name),
+            Diff::SetInnerHtml(html) => {
+                for child in el.children.drain(..) {
+                    self.call_hooks(&child, "unmount");
+                }
+                if !html.is_empty() {
+                    el.children.push(TestNode::RawHtml(html));
+                }
+            }
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<A: App> Runtime<A> for TestRuntime<A> {
+    fn get_env<'a>(&'a self) -> &'a Env<A> {
+        &self.env
+    }
+
+    fn schedule_render(&self) {
+        self.run();
+    }
+
+    fn handle_diff(&self, diff: Diff) {
+        let mut root = self.root.borrow_mut();
+        self.handle_diff_inner(&mut root, diff);
+        self.sync_refs(&root);
+    }
+
+    fn handle_future(&self, future: LocalBoxFuture<'static, ()>) {
+        self.spawner.spawn_local(future).unwrap();
+    }
+
+    fn report_error(&self, error: TaskError) {
+        panic!("unhandled task error: {}", error);
+    }
+}
@@ -1,5 +1,10 @@
-use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hash::FxHashMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::iter::FromIterator;
+use std::rc::Rc;
+
+use crate::lazy::{fill_lazies, Lazy, LazyMap, Resolver};
+use crate::node_ref::Ref;
 
 pub use serde_json::Value as HandlerArg;
 
@@ -10,24 +15,157 @@ fn diff_attributes(a: &mut Vec<Attribute>, b: &[Attribute]) -> Vec<Diff> {
 
     let mut old_map = FxHashMap::<String, AttributeValue>::from_iter(a.drain(..));
     for &(ref new_key, ref new_val) in b {
-        match old_map.remove(new_key) {
-            Some(old_val) => {
-                if &old_val != new_val {
-                    result.push(Diff::SetAttribute(new_key.to_owned(), new_val.to_owned()))
+        let old_val = old_map.remove(new_key).unwrap_or(AttributeValue::None);
+        diff_attribute(new_key, &old_val, new_val, &mut result);
+    }
+
+    for (old_key, old_val) in old_map.drain() {
+        diff_attribute(&old_key, &old_val, &AttributeValue::None, &mut result);
+    }
+
+    result
+}
+
+const PROPERTY_PREFIX: &str = "prop:";
+
+/// Name of DOM property if attribute is named with `prop:` prefix
+pub fn property_name(name: &str) -> Option<&str> {
+    name.strip_prefix(PROPERTY_PREFIX)
+}
+
+fn diff_attribute(name: &str, a: &AttributeValue, b: &AttributeValue, result: &mut Vec<Diff>) {
+    match (property_name(name), a, b) {
+        _ if a == b => (),
+        (Some(property), _, AttributeValue::None) => {
+            result.push(Diff::RemoveProperty(property.to_owned()))
+        }
+        (Some(property), _, _) => result.push(Diff::SetProperty(property.to_owned(), b.to_owned())),
+        _ => diff_attribute_value(name, a, b, result),
+    }
+}
+
+fn diff_attribute_value(name: &str, a: &AttributeValue, b: &AttributeValue, result: &mut Vec<Diff>) {
+    match (name, a, b) {
+        (_, _, AttributeValue::None) => result.push(Diff::RemoveAttribute(name.to_owned())),
+        ("class", AttributeValue::ClassList(a), AttributeValue::ClassList(b)) => {
+            for class in a.difference(b) {
+                result.push(Diff::RemoveClass(class.to_owned()));
+            }
+            for class in b.difference(a) {
+                result.push(Diff::AddClass(class.to_owned()));
+            }
+        }
+        ("style", AttributeValue::Style(a), AttributeValue::Style(b)) => {
+            for property in a.keys().filter(|property| !b.contains_key(*property)) {
+                result.push(Diff::RemoveStyle(property.to_owned()));
+            }
+            for (property, value) in b {
+                if a.get(property) != Some(value) {
+                    result.push(Diff::SetStyle(property.to_owned(), value.to_owned()));
                 }
             }
-            None => result.push(Diff::SetAttribute(new_key.to_owned(), new_val.to_owned())),
         }
+        _ => result.push(Diff::SetAttribute(name.to_owned(), b.to_owned())),
     }
+}
 
-    for (old_key, _) in old_map.drain() {
-        result.push(Diff::RemoveAttribute(old_key));
+fn find_attribute<'a>(attributes: &'a mut Vec<Attribute>, name: &str, empty: AttributeValue) -> &'a mut AttributeValue {
+    let i = match attributes.iter().position(|(k, _)| k == name) {
+        Some(i) => i,
+        None => {
+            attributes.push((name.to_owned(), empty));
+            attributes.len() - 1
+        }
+    };
+    &mut attributes[i].1
+}
+
+/// Apply diff of attribute in the same way as runtime does to DOM, and return whether `diff` is about attribute
+pub(crate) fn apply_attribute(attributes: &mut Vec<Attribute>, diff: &Diff) -> bool {
+    match diff {
+        Diff::SetAttribute(name, value) => {
+            *find_attribute(attributes, name, AttributeValue::None) = value.to_owned();
+        }
+        Diff::RemoveAttribute(name) => attributes.retain(|&(ref k, _)| k != name),
+        Diff::SetProperty(name, value) => {
+            let name = format!("{}{}", PROPERTY_PREFIX, name);
+            *find_attribute(attributes, &name, AttributeValue::None) = value.to_owned();
+        }
+        Diff::RemoveProperty(name) => {
+            let name = format!("{}{}", PROPERTY_PREFIX, name);
+            attributes.retain(|(k, _)| k != &name);
+        }
+        Diff::AddClass(class) | Diff::RemoveClass(class) => {
+            match find_attribute(attributes, "class", AttributeValue::ClassList(BTreeSet::new())) {
+                AttributeValue::ClassList(class_list) => {
+                    if let Diff::AddClass(_) = diff {
+                        class_list.insert(class.to_owned());
+                    } else {
+                        class_list.remove(class);
+                    }
+                }
+                value => panic!("class list is patched to {:?}", value),
+            }
+        }
+        Diff::SetStyle(property, _) | Diff::RemoveStyle(property) => {
+            match find_attribute(attributes, "style", AttributeValue::Style(BTreeMap::new())) {
+                AttributeValue::Style(style) => {
+                    if let Diff::SetStyle(_, value) = diff {
+                        style.insert(property.to_owned(), value.to_owned());
+                    } else {
+                        style.remove(property);
+                    }
+                }
+                value => panic!("style is patched to {:?}", value),
+            }
+        }
+        _ => return false,
     }
+    true
+}
 
-    result
+/// Names of handlers which runtime calls on lifecycle of element, instead of attaching them to DOM event.
+/// `mount` is called after element is created, `update` after element or its descendants are patched,
+/// and `unmount` after element is removed. They are called after all diffs of the render are applied.
+pub const LIFECYCLE_HOOKS: &[&str] = &["mount", "update", "unmount"];
+
+/// Options of event listener, which are given as suffixes of handler name such as `submit|prevent`.
+/// Handler with different options is different handler, so changing options reattaches the listener.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ListenerOptions {
+    /// Call `preventDefault` of event before handler
+    pub prevent: bool,
+    /// Call `stopPropagation` of event before handler
+    pub stop: bool,
+    pub capture: bool,
+    pub passive: bool,
+    /// Remove listener after it is called once
+    pub once: bool,
+}
+
+/// Split handler name into event name and listener options, such as `click|stop` into `click` and `stop`.
+/// Unknown options are ignored. `passive` is dropped when `prevent` is given,
+/// since browser ignores `preventDefault` of passive listener.
+pub fn listener_options(name: &str) -> (&str, ListenerOptions) {
+    let mut iter = name.split('|');
+    let event = iter.next().unwrap_or("");
+    let mut options = ListenerOptions::default();
+    for option in iter {
+        match option {
+            "prevent" => options.prevent = true,
+            "stop" => options.stop = true,
+            "capture" => options.capture = true,
+            "passive" => options.passive = true,
+            "once" => options.once = true,
+            _ => (),
+        }
+    }
+    options.passive &= !options.prevent;
+    (event, options)
 }
 
 pub(crate) type HandlerFunction<A> = Box<Fn(HandlerArg) -> Option<A>>;
+type SharedHandler<A> = Rc<dyn Fn(HandlerArg) -> Option<A>>;
 type Handler = (String, String);
 
 fn diff_handlers(a: &mut Vec<Handler>, b: &[Handler]) -> Vec<Diff> {
@@ -35,8 +173,9 @@ fn diff_handlers(a: &mut Vec<Handler>, b: &[Handler]) -> Vec<Diff> {
 
     let mut old_map = FxHashMap::<String, String>::from_iter(a.drain(..));
     for &(ref new_key, ref new_id) in b {
-        old_map.remove(new_key);
-        result.push(Diff::SetHandler(new_key.to_owned(), new_id.to_owned()));
+        if old_map.remove(new_key).as_ref() != Some(new_id) {
+            result.push(Diff::SetHandler(new_key.to_owned(), new_id.to_owned()));
+        }
     }
 
     for (old_key, old_id) in old_map.drain() {
@@ -46,117 +185,341 @@ fn diff_handlers(a: &mut Vec<Handler>, b: &[Handler]) -> Vec<Diff> {
     result
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub enum Node {
     Text(String),
     Element(Element),
+    /// Siblings without wrapper element, which are flattened into parent on rendering
+    Fragment(Vec<Node>),
+    Lazy(Lazy),
+    /// Markup which is set as `innerHTML` of parent element.
+    /// It should be the only child of the element, otherwise it is rendered as text.
+    RawHtml(String),
     Null,
 }
 
+/// Flatten fragments and drop `Node::Null`.
+/// Lazy node which renders single node is kept when `keep_lazy`, so that diff can skip reused one.
+fn flatten_into(nodes: Vec<Node>, out: &mut Vec<Node>, keep_lazy: bool) {
+    for node in nodes {
+        match node {
+            Node::Fragment(children) => flatten_into(children, out, keep_lazy),
+            Node::Lazy(lazy) => match lazy.node() {
+                Some(Node::Element(_)) | Some(Node::Text(_)) if keep_lazy => out.push(Node::Lazy(lazy)),
+                Some(node) => flatten_into(vec![node.to_owned()], out, keep_lazy),
+                None => (),
+            },
+            Node::Null => (),
+            node => out.push(node),
+        }
+    }
+}
+
+fn flatten_ref_into<'a>(nodes: &'a [Node], out: &mut Vec<&'a Node>, keep_lazy: bool) {
+    for node in nodes {
+        match node {
+            Node::Fragment(children) => flatten_ref_into(children, out, keep_lazy),
+            Node::Lazy(lazy) => match lazy.node() {
+                Some(Node::Element(_)) | Some(Node::Text(_)) if keep_lazy => out.push(node),
+                Some(node) => flatten_ref_into(std::slice::from_ref(node), out, keep_lazy),
+                None => (),
+            },
+            Node::Null => (),
+            node => out.push(node),
+        }
+    }
+}
+
 impl Node {
-    pub(crate) fn diff(a: &mut Node, b: &Node, i: &mut usize) -> Option<Diff> {
+    /// Diffs to turn `a` into `b`, for parent which has only `a` as its child.
+    /// Fragment is flattened into the parent, so more than one diff can be emitted.
+    /// `a` is consumed while diffing.
+    pub fn diff(a: &mut Node, b: &Node) -> Vec<Diff> {
+        let a = std::mem::replace(a, Node::Null);
+        diff_children(&mut vec![a], std::slice::from_ref(b))
+    }
+
+    /// Nodes which are actually rendered for `nodes`, where fragments and lazy nodes are flattened and `Node::Null` is dropped
+    pub fn flatten(nodes: &[Node]) -> Vec<&Node> {
+        let mut out = vec![];
+        flatten_ref_into(nodes, &mut out, false);
+        out
+    }
+
+    /// Diff of rendered node `a` at `i`, which is matched with `b`
+    fn diff_rendered(a: &mut Node, b: &Node, i: usize) -> Option<Diff> {
         match (a, b) {
-            (&mut Node::Element(ref mut a), &Node::Element(ref b)) => {
-                match Element::diff(a, b, *i) {
-                    Some(diff) => Some(diff),
-                    None => None,
-                }
+            (&mut Node::Lazy(ref a), &Node::Lazy(ref b)) if a.shares_node(b) => None,
+            (&mut Node::Lazy(ref a), b) => {
+                let mut a = a.node().unwrap().to_owned();
+                Node::diff_rendered(&mut a, b, i)
             }
-            (&mut Node::Text(ref mut text_a), &Node::Text(ref text_b)) => {
+            (a, &Node::Lazy(ref b)) => Node::diff_rendered(a, b.node().unwrap(), i),
+            (&mut Node::Element(ref mut a), &Node::Element(ref b)) => Element::diff(a, b, i),
+            (&mut Node::Text(ref mut text_a), &Node::Text(ref text_b))
+            | (&mut Node::RawHtml(ref mut text_a), &Node::RawHtml(ref text_b)) => {
                 if text_a == text_b {
                     return None;
                 }
-                Some(Diff::ReplaceChild(*i, b.to_owned()))
+                Some(Diff::ReplaceChild(i, b.to_owned()))
             }
-            (&mut Node::Null, &Node::Null) => None,
-            (&mut Node::Null, _) => Some(Diff::AddChild(*i, b.to_owned())),
-            (_, &Node::Null) => Some(Diff::RemoveChild(*i)),
-            _ => Some(Diff::ReplaceChild(*i, b.to_owned())),
+            _ => Some(Diff::ReplaceChild(i, b.to_owned())),
         }
     }
 
-    fn get_key(&self) -> Option<String> {
+    /// Apply `diff` emitted for children of this node, in the same way as runtime patches rendered tree.
+    /// Child indices count rendered children only, so fragments and lazy nodes are flattened and `Node::Null` children are dropped.
+    ///
+    /// Panics if `diff` doesn't fit this node.
+    pub fn apply(&mut self, diff: &Diff) {
+        let el = match self {
+            Node::Element(ref mut el) => el,
+            _ => panic!("diff is applied to non-element node: {:?}", diff),
+        };
+        let children = std::mem::replace(&mut el.children, vec![]);
+        flatten_into(children, &mut el.children, false);
+        if apply_attribute(&mut el.attributes, diff) {
+            return;
+        }
+
+        match diff {
+            Diff::AddChild(i, node) => {
+                let mut nodes = vec![];
+                flatten_into(vec![node.to_owned()], &mut nodes, false);
+                el.children.splice(*i..*i, nodes);
+            }
+            Diff::ReplaceChild(i, node) => {
+                let mut nodes = vec![];
+                flatten_into(vec![node.to_owned()], &mut nodes, false);
+                el.children.splice(*i..=*i, nodes);
+            }
+            Diff::RemoveChild(i) => {
+                el.children.remove(*i);
+            }
+            Diff::MoveChild(from, to) => {
+                let child = el.children.remove(*from);
+                el.children.insert(*to, child);
+            }
+            Diff::PatchChild(i, diffs) => {
+                for diff in diffs {
+                    el.children[*i].apply(diff);
+                }
+            }
+            Diff::SetHandler(name, id) => {
+                match el.handlers.iter_mut().find(|&&mut (ref n, _)| n == name) {
+                    Some(handler) => handler.1 = id.to_owned(),
+                    None => el.handlers.push((name.to_owned(), id.to_owned())),
+                }
+            }
+            Diff::RemoveHandler(name, _) => el.handlers.retain(|&(ref n, _)| n != name),
+            Diff::SetInnerHtml(html) if html.is_empty() => el.children.clear(),
+            Diff::SetInnerHtml(html) => el.children = vec![Node::RawHtml(html.to_owned())],
+            _ => unreachable!(),
+        }
+    }
+
+    pub(crate) fn get_key(&self) -> Option<String> {
         match self {
             Node::Element(ref el) => el.get_key(),
+            Node::Lazy(ref lazy) => lazy.node().and_then(|node| node.get_key()),
             _ => None,
         }
     }
 }
 
-fn get_nodelist_key_set(nodelist: &[Node]) -> FxHashSet<String> {
-    FxHashSet::from_iter(nodelist.iter().filter_map(|c| c.get_key()))
+/// Indices of `seq` which form one of its longest strictly increasing subsequences
+fn longest_increasing_subsequence(seq: &[usize]) -> Vec<bool> {
+    // `tails[l]` is index of the smallest tail of increasing subsequences with length `l + 1`
+    let mut tails: Vec<usize> = vec![];
+    let mut prev = vec![None; seq.len()];
+    for (i, &v) in seq.iter().enumerate() {
+        let l = match tails.binary_search_by(|&t| seq[t].cmp(&v)) {
+            Ok(l) | Err(l) => l,
+        };
+        if l > 0 {
+            prev[i] = Some(tails[l - 1]);
+        }
+        if l == tails.len() {
+            tails.push(i);
+        } else {
+            tails[l] = i;
+        }
+    }
+
+    let mut result = vec![false; seq.len()];
+    let mut cursor = tails.last().cloned();
+    while let Some(i) = cursor {
+        result[i] = true;
+        cursor = prev[i];
+    }
+    result
 }
 
-fn diff_children(a: &mut Vec<Node>, b: &[Node], i: &mut usize) -> Vec<Diff> {
+/// Emit diffs to turn rendered children `a` into `b`.
+///
+/// Keyed child is matched with old child with same key, and others are matched by order among
+/// unkeyed children. Children sharing a key are matched by order as well. Matched children which keep their relative order are left in place,
+/// and the rest are moved by `Diff::MoveChild`. Indices in emitted diffs are positions in
+/// the rendered children at the time each diff is applied, so fragments are flattened and
+/// `Node::Null` is not counted.
+fn diff_children(a: &mut Vec<Node>, b: &[Node]) -> Vec<Diff> {
     let mut result = vec![];
-    let b_key_set = get_nodelist_key_set(b);
-    let survived = a
-        .drain(..)
-        .filter(|c| match c.get_key() {
-            Some(k) => {
-                let is_survived = b_key_set.contains(&k);
-                if !is_survived {
-                    result.push(Diff::RemoveChild(*i));
-                    return false;
-                }
-                *i += 1;
-                true
-            }
-            None => {
-                *i += 1;
-                true
-            }
+    let mut flattened = vec![];
+    flatten_into(a.drain(..).collect(), &mut flattened, true);
+    let mut old: Vec<Option<Node>> = flattened.into_iter().map(Some).collect();
+    let mut new = vec![];
+    flatten_ref_into(b, &mut new, true);
+
+    // Old indices in reverse order for each key, and for unkeyed children
+    let mut keyed = FxHashMap::<String, Vec<usize>>::default();
+    let mut unkeyed = vec![];
+    for (i, child) in old.iter().enumerate().rev() {
+        match child.as_ref().and_then(|c| c.get_key()) {
+            Some(key) => keyed.entry(key).or_default().push(i),
+            None => unkeyed.push(i),
+        }
+    }
+
+    // Old index matched with each new child
+    let sources: Vec<Option<usize>> = new
+        .iter()
+        .map(|child| match child.get_key() {
+            Some(key) => keyed.get_mut(&key).and_then(|indices| indices.pop()),
+            None => unkeyed.pop(),
         })
         .collect();
-    *a = survived;
 
-    let mut i = 0;
-    a.reverse();
-    for new_child in b.iter() {
-        match a.pop() {
+    let mut matched = vec![false; old.len()];
+    for &j in sources.iter().flatten() {
+        matched[j] = true;
+    }
+    for j in (0..old.len()).rev() {
+        if !matched[j] {
+            result.push(Diff::RemoveChild(j));
+        }
+    }
+
+    // Rendered children after removal, identified by old index or `None` for added one
+    let mut current: Vec<Option<usize>> = (0..old.len()).filter(|&j| matched[j]).map(Some).collect();
+    let stable = longest_increasing_subsequence(&sources.iter().flatten().cloned().collect::<Vec<_>>());
+    let mut stable = stable.into_iter().rev();
+
+    // Place children from the last one, each just before the child placed previously
+    let mut anchor = current.len();
+    for (new_child, source) in new.iter().rev().zip(sources.iter().rev()) {
+        let source = match *source {
+            Some(source) => source,
             None => {
-                result.push(Diff::AddChild(i, new_child.to_owned()));
-                i += 1;
+                let new_child = match new_child {
+                    Node::Lazy(lazy) => lazy.node().unwrap(),
+                    node => node,
+                };
+                result.push(Diff::AddChild(anchor, new_child.to_owned()));
+                current.insert(anchor, None);
+                continue;
             }
-            Some(mut old_child) => {
-                if let Some(diff) = Node::diff(&mut old_child, new_child, &mut i) {
-                    result.push(diff.to_owned());
-                    if let Diff::RemoveChild(_) = diff {
-                        continue;
-                    }
-                }
+        };
+
+        let mut i = current.iter().position(|&c| c == Some(source)).unwrap();
+        if !stable.next().unwrap() {
+            let to = if i < anchor { anchor - 1 } else { anchor };
+            if i != to {
+                result.push(Diff::MoveChild(i, to));
+                let moved = current.remove(i);
+                current.insert(to, moved);
             }
+            i = to;
         }
-        i += 1;
+        let mut old_child = old[source].take().unwrap();
+        if let Some(diff) = Node::diff_rendered(&mut old_child, new_child, i) {
+            result.push(diff);
+        }
+        anchor = i;
     }
 
-    for _ in a.iter() {
-        result.push(Diff::RemoveChild(i));
+    result
+}
+
+/// Namespace of element, which is inferred from `<svg>` and `<math>` and inherited by their descendants
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Namespace {
+    Html,
+    Svg,
+    MathMl,
+}
+
+impl Namespace {
+    pub fn uri(self) -> &'static str {
+        match self {
+            Namespace::Html => "http://www.w3.org/1999/xhtml",
+            Namespace::Svg => "http://www.w3.org/2000/svg",
+            Namespace::MathMl => "http://www.w3.org/1998/Math/MathML",
+        }
     }
 
-    result
+    /// Namespace which element named `name` starts
+    fn of_root(name: &str) -> Option<Namespace> {
+        match name {
+            "svg" => Some(Namespace::Svg),
+            "math" => Some(Namespace::MathMl),
+            _ => None,
+        }
+    }
+}
+
+/// Let `node` inherit `namespace` from its parent, unless it starts another namespace
+pub(crate) fn inherit_namespace(node: &mut Node, namespace: Namespace) {
+    match node {
+        Node::Element(el) => {
+            if el.namespace != namespace && Namespace::of_root(&el.name).is_none() {
+                el.set_namespace(namespace);
+            }
+        }
+        Node::Fragment(children) => {
+            for child in children.iter_mut() {
+                inherit_namespace(child, namespace);
+            }
+        }
+        Node::Lazy(lazy) => lazy.namespace = namespace,
+        _ => (),
+    }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct Element {
     name: String,
+    namespace: Namespace,
     attributes: Vec<Attribute>,
-    handlers: Vec<Handler>,
-    children: Vec<Node>,
+    pub(crate) handlers: Vec<Handler>,
+    pub(crate) children: Vec<Node>,
 }
 
 impl Element {
-    fn new(
+    pub fn new(
         name: String,
         attributes: Vec<Attribute>,
         handlers: Vec<Handler>,
         children: Vec<Node>,
     ) -> Element {
-        Element {
+        let mut el = Element {
             name,
+            namespace: Namespace::Html,
             attributes,
             handlers,
             children,
+        };
+        if let Some(namespace) = Namespace::of_root(&el.name) {
+            el.set_namespace(namespace);
+        }
+        el
+    }
+
+    fn set_namespace(&mut self, namespace: Namespace) {
+        self.namespace = namespace;
+        // Content of `<foreignObject>` is back to HTML
+        let namespace = if self.name == "foreignObject" { Namespace::Html } else { namespace };
+        for child in self.children.iter_mut() {
+            inherit_namespace(child, namespace);
         }
     }
 
@@ -164,6 +527,10 @@ impl Element {
         &self.name
     }
 
+    pub fn namespace(&self) -> Namespace {
+        self.namespace
+    }
+
     pub fn attributes(&self) -> &[Attribute] {
         &self.attributes
     }
@@ -176,6 +543,22 @@ impl Element {
         &self.children
     }
 
+    /// Markup of `Node::RawHtml` which is the only rendered child, to be set as `innerHTML`
+    pub fn raw_html(&self) -> Option<&str> {
+        match Node::flatten(&self.children).as_slice() {
+            [Node::RawHtml(html)] => Some(html),
+            _ => None,
+        }
+    }
+
+    /// Whether element has `unmanaged` attribute, whose children are never patched after created,
+    /// such as container of third-party widget
+    pub fn is_unmanaged(&self) -> bool {
+        self.attributes
+            .iter()
+            .any(|(k, v)| k == "unmanaged" && v == &AttributeValue::Bool(true))
+    }
+
     fn diff(a: &mut Element, b: &Element, i: usize) -> Option<Diff> {
         if let (Some(a_key), Some(b_key)) = (a.get_key(), b.get_key()) {
             if a_key != b_key {
@@ -183,15 +566,30 @@ impl Element {
             }
         }
 
-        if a.name != b.name {
+        if a.name != b.name || a.namespace != b.namespace || a.is_unmanaged() != b.is_unmanaged() {
             return Some(Diff::ReplaceChild(i, Node::Element(b.to_owned())));
         }
 
         let mut result = vec![];
 
+        let unmanaged = b.is_unmanaged();
+        let raw_html = (a.raw_html().map(str::to_owned), b.raw_html());
         result.append(&mut diff_attributes(&mut a.attributes, &b.attributes));
         result.append(&mut diff_handlers(&mut a.handlers, &b.handlers));
-        result.append(&mut diff_children(&mut a.children, &b.children, &mut 0));
+        match raw_html {
+            _ if unmanaged => (),
+            (None, None) => result.append(&mut diff_children(&mut a.children, &b.children)),
+            (Some(a_html), Some(b_html)) => {
+                if a_html != b_html {
+                    result.push(Diff::SetInnerHtml(b_html.to_owned()));
+                }
+            }
+            (None, Some(b_html)) => result.push(Diff::SetInnerHtml(b_html.to_owned())),
+            (Some(_), None) => {
+                result.push(Diff::SetInnerHtml(String::new()));
+                result.append(&mut diff_children(&mut vec![], &b.children));
+            }
+        }
 
         if result.is_empty() {
             return None;
@@ -205,7 +603,8 @@ impl Element {
             .find(|&&(ref k, _)| k == "key")
             .and_then(|&(_, ref v)| match v {
                 AttributeValue::String(ref s) => Some(s.to_owned()),
-                AttributeValue::Bool(_) => None,
+                AttributeValue::Number(n) => Some(n.to_string()),
+                _ => None,
             })
     }
 }
@@ -214,18 +613,104 @@ impl Element {
 pub enum Diff {
     SetAttribute(String, AttributeValue),
     RemoveAttribute(String),
+    /// Set DOM property, which is given as attribute with `prop:` prefix
+    SetProperty(String, AttributeValue),
+    /// Reset DOM property to its default value
+    RemoveProperty(String),
+    AddClass(String),
+    RemoveClass(String),
+    SetStyle(String, String),
+    RemoveStyle(String),
     AddChild(usize, Node),
     ReplaceChild(usize, Node),
     RemoveChild(usize),
+    /// Move child at first index so that it is placed at second index after the move
+    MoveChild(usize, usize),
     PatchChild(usize, Vec<Diff>),
     SetHandler(String, String),
     RemoveHandler(String, String),
+    /// Replace all children with markup, which is emitted for `Node::RawHtml`
+    SetInnerHtml(String),
+}
+
+/// HTML attributes whose presence means true
+const BOOLEAN_ATTRIBUTES: &[&str] = &[
+    "allowfullscreen", "async", "autofocus", "autoplay", "checked", "controls", "default", "defer",
+    "disabled", "formnovalidate", "hidden", "inert", "ismap", "itemscope", "loop", "multiple",
+    "muted", "nomodule", "novalidate", "open", "playsinline", "readonly", "required", "reversed",
+    "selected",
+];
+
+/// Whether attribute `name` is HTML boolean attribute, which is removed for `false`
+pub fn is_boolean_attribute(name: &str) -> bool {
+    BOOLEAN_ATTRIBUTES.contains(&name)
 }
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum AttributeValue {
     String(String),
     Bool(bool),
+    Number(f64),
+    /// Classes of `class` attribute, which are patched one by one
+    ClassList(BTreeSet<String>),
+    /// Properties of `style` attribute, which are patched one by one
+    Style(BTreeMap<String, String>),
+    /// Absent attribute
+    None,
+    /// Handle which runtime sets rendered element to, instead of setting attribute
+    Ref(Ref),
+}
+
+impl AttributeValue {
+    /// Make class list from names of classes.
+    /// Each name is split by whitespace, and empty ones are dropped, as DOM rejects them.
+    pub fn class_list<I, S>(classes: I) -> AttributeValue
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        let mut class_list = BTreeSet::new();
+        for class in classes {
+            class_list.extend(class.into().split_whitespace().map(str::to_owned));
+        }
+        AttributeValue::ClassList(class_list)
+    }
+
+    /// Make style from pairs of property and value
+    pub fn style<I, K, V>(properties: I) -> AttributeValue
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: Into<String>,
+        V: Into<String>,
+    {
+        AttributeValue::Style(properties.into_iter().map(|(k, v)| (k.into(), v.into())).collect())
+    }
+
+    /// Value written into DOM attribute `name`, or `None` for absent attribute.
+    /// `Bool` of boolean attribute such as `disabled` is given as presence of the attribute,
+    /// and others such as `aria-hidden` and `draggable` are given as `"true"` or `"false"`.
+    pub fn to_attribute_string(&self, name: &str) -> Option<String> {
+        match self {
+            AttributeValue::String(s) => Some(s.to_owned()),
+            AttributeValue::Bool(b) if is_boolean_attribute(name) => match b {
+                true => Some(String::new()),
+                false => None,
+            },
+            AttributeValue::Bool(b) => Some(b.to_string()),
+            AttributeValue::Number(n) => Some(n.to_string()),
+            AttributeValue::ClassList(class_list) => {
+                Some(class_list.iter().map(String::as_str).collect::<Vec<_>>().join(" "))
+            }
+            AttributeValue::Style(style) => Some(
+                style
+                    .iter()
+                    .map(|(property, value)| format!("{}: {};", property, value))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            ),
+            AttributeValue::None | AttributeValue::Ref(_) => None,
+        }
+    }
 }
 
 impl From<String> for AttributeValue {
@@ -246,11 +731,74 @@ impl From<bool> for AttributeValue {
     }
 }
 
-pub(crate) type HandlerMap<A> = FxHashMap<String, HandlerFunction<A>>;
+macro_rules! impl_from_number {
+    ($($t:ty),*) => {
+        $(
+            impl From<$t> for AttributeValue {
+                fn from(n: $t) -> AttributeValue {
+                    AttributeValue::Number(n as f64)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_number!(i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64);
+
+impl From<BTreeSet<String>> for AttributeValue {
+    fn from(class_list: BTreeSet<String>) -> AttributeValue {
+        AttributeValue::class_list(class_list)
+    }
+}
+
+impl From<Vec<String>> for AttributeValue {
+    fn from(classes: Vec<String>) -> AttributeValue {
+        AttributeValue::class_list(classes)
+    }
+}
+
+impl<'a> From<Vec<&'a str>> for AttributeValue {
+    fn from(classes: Vec<&'a str>) -> AttributeValue {
+        AttributeValue::class_list(classes)
+    }
+}
+
+impl From<BTreeMap<String, String>> for AttributeValue {
+    fn from(style: BTreeMap<String, String>) -> AttributeValue {
+        AttributeValue::Style(style)
+    }
+}
+
+impl<'a> From<Vec<(&'a str, String)>> for AttributeValue {
+    fn from(style: Vec<(&'a str, String)>) -> AttributeValue {
+        AttributeValue::style(style)
+    }
+}
+
+impl<'a, 'b> From<Vec<(&'a str, &'b str)>> for AttributeValue {
+    fn from(style: Vec<(&'a str, &'b str)>) -> AttributeValue {
+        AttributeValue::style(style)
+    }
+}
+
+impl From<Ref> for AttributeValue {
+    fn from(r: Ref) -> AttributeValue {
+        AttributeValue::Ref(r)
+    }
+}
+
+impl<T: Into<AttributeValue>> From<Option<T>> for AttributeValue {
+    fn from(value: Option<T>) -> AttributeValue {
+        value.map_or(AttributeValue::None, Into::into)
+    }
+}
+
+pub(crate) type HandlerMap<A> = FxHashMap<String, SharedHandler<A>>;
 
 pub struct View<A> {
     pub(crate) node: Node,
     pub(crate) handler_map: HandlerMap<A>,
+    pub(crate) lazy_map: LazyMap<A>,
 }
 
 pub enum Child<A> {
@@ -276,6 +824,31 @@ impl<A> FromIterator<View<A>> for Child<A> {
     }
 }
 
+fn collect_children<A>(
+    children: Vec<Child<A>>,
+    handler_map: &mut HandlerMap<A>,
+    lazy_map: &mut LazyMap<A>,
+) -> Vec<Node> {
+    let mut children_vec = vec![];
+    for child in children {
+        match child {
+            Child::View(v) => {
+                handler_map.extend(v.handler_map);
+                lazy_map.extend(v.lazy_map);
+                children_vec.push(v.node);
+            }
+            Child::ViewList(child_vec) => {
+                for v in child_vec {
+                    handler_map.extend(v.handler_map);
+                    lazy_map.extend(v.lazy_map);
+                    children_vec.push(v.node);
+                }
+            }
+        }
+    }
+    children_vec
+}
+
 impl<A> View<A> {
     pub fn new(
         name: String,
@@ -288,30 +861,31 @@ impl<A> View<A> {
             .into_iter()
             .map(|(kind, (id, f))| {
                 let handler = (kind, id.to_owned());
-                handler_map.insert(id, f);
+                handler_map.insert(id, Rc::from(f));
                 handler
             })
             .collect();
 
-        let mut children_vec = vec![];
-        for child in children {
-            match child {
-                Child::View(v) => {
-                    handler_map.extend(v.handler_map);
-                    children_vec.push(v.node);
-                }
-                Child::ViewList(child_vec) => {
-                    for v in child_vec {
-                        handler_map.extend(v.handler_map);
-                        children_vec.push(v.node);
-                    }
-                }
-            }
-        }
+        let mut lazy_map = FxHashMap::default();
+        let children_vec = collect_children(children, &mut handler_map, &mut lazy_map);
 
         View {
             node: Node::Element(Element::new(name, attributes, handlers, children_vec)),
             handler_map,
+            lazy_map,
+        }
+    }
+
+    /// Siblings without wrapper element
+    pub fn fragment(children: Vec<Child<A>>) -> View<A> {
+        let mut handler_map = FxHashMap::default();
+        let mut lazy_map = FxHashMap::default();
+        let children_vec = collect_children(children, &mut handler_map, &mut lazy_map);
+
+        View {
+            node: Node::Fragment(children_vec),
+            handler_map,
+            lazy_map,
         }
     }
 
@@ -319,6 +893,17 @@ impl<A> View<A> {
         View {
             node: Node::Text(s),
             handler_map: FxHashMap::default(),
+            lazy_map: FxHashMap::default(),
+        }
+    }
+
+    /// Markup which is set as `innerHTML` of parent element, such as sanitized output of markdown.
+    /// It should be the only child of the element.
+    pub fn raw_html(html: String) -> View<A> {
+        View {
+            node: Node::RawHtml(html),
+            handler_map: FxHashMap::default(),
+            lazy_map: FxHashMap::default(),
         }
     }
 
@@ -326,6 +911,98 @@ impl<A> View<A> {
         View {
             node: Node::Null,
             handler_map: FxHashMap::default(),
+            lazy_map: FxHashMap::default(),
+        }
+    }
+
+    /// Call `f` after root element of this view is created, such as to focus or measure it with `Ref`.
+    /// It is ignored if this view is not element.
+    pub fn on_mount<F>(self, f: F) -> View<A>
+    where
+        F: Fn(HandlerArg) -> Option<A> + 'static,
+    {
+        self.hook("mount", f)
+    }
+
+    /// Call `f` after root element of this view or its descendants are patched
+    pub fn on_update<F>(self, f: F) -> View<A>
+    where
+        F: Fn(HandlerArg) -> Option<A> + 'static,
+    {
+        self.hook("update", f)
+    }
+
+    /// Call `f` after root element of this view is removed
+    pub fn on_unmount<F>(self, f: F) -> View<A>
+    where
+        F: Fn(HandlerArg) -> Option<A> + 'static,
+    {
+        self.hook("unmount", f)
+    }
+
+    fn hook<F>(mut self, name: &str, f: F) -> View<A>
+    where
+        F: Fn(HandlerArg) -> Option<A> + 'static,
+    {
+        if let Node::Element(ref mut el) = self.node {
+            let (id, f) = crate::handler(f);
+            el.handlers.retain(|(n, _)| n != name);
+            el.handlers.push((name.to_owned(), id.to_owned()));
+            self.handler_map.insert(id, Rc::from(f));
+        }
+        self
+    }
+
+    /// Render lazy nodes or reuse ones of `old_node`, and replace handler ids with ones derived
+    /// from position of element, so that handler of unchanged element keeps its id across renders.
+    pub(crate) fn resolve(&mut self, old_node: &Node, old_handler_map: HandlerMap<A>) {
+        Resolver::new(old_node, old_handler_map).resolve(self);
+    }
+
+    /// Node whose lazy subtrees are rendered, without handlers
+    pub(crate) fn to_node(&self) -> Node {
+        let mut node = self.node.to_owned();
+        fill_lazies(&mut node, &self.lazy_map);
+        node
+    }
+
+    /// Lift handlers into other action type, such as to embed view of child app into parent.
+    pub fn map<B, F>(self, f: F) -> View<B>
+    where
+        A: 'static,
+        B: 'static,
+        F: Fn(A) -> B + 'static,
+    {
+        self.map_rc(Rc::new(f))
+    }
+
+    fn map_rc<B: 'static>(self, f: Rc<dyn Fn(A) -> B>) -> View<B>
+    where
+        A: 'static,
+    {
+        let handler_map = self
+            .handler_map
+            .into_iter()
+            .map(|(id, handler)| {
+                let f = f.clone();
+                let mapped: SharedHandler<B> = Rc::new(move |arg| handler(arg).map(&*f));
+                (id, mapped)
+            })
+            .collect();
+        // Lazy subtree is rendered later, so its view is mapped at that time
+        let lazy_map = self
+            .lazy_map
+            .into_iter()
+            .map(|(id, render)| {
+                let f = f.clone();
+                let mapped: Rc<dyn Fn() -> View<B>> = Rc::new(move || render().map_rc(f.clone()));
+                (id, mapped)
+            })
+            .collect();
+        View {
+            node: self.node,
+            handler_map,
+            lazy_map,
         }
     }
 }
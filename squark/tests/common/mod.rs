@@ -0,0 +1,59 @@
+// Each test crate uses only some of the fixtures
+#![allow(dead_code)]
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use futures::future::LocalBoxFuture;
+use squark::{App, Diff, Env, Runtime, Task, View};
+
+/// Runtime which records diffs instead of applying them, to test diffs emitted by render
+#[derive(Clone)]
+pub struct RecordingRuntime<A: App> {
+    env: Env<A>,
+    pub diffs: Rc<RefCell<Vec<Diff>>>,
+}
+
+impl<A: App> RecordingRuntime<A> {
+    pub fn new(state: A::State) -> RecordingRuntime<A> {
+        RecordingRuntime {
+            env: Env::new(state),
+            diffs: Rc::new(RefCell::new(vec![])),
+        }
+    }
+}
+
+impl<A: App> Runtime<A> for RecordingRuntime<A> {
+    fn get_env(&self) -> &Env<A> {
+        &self.env
+    }
+
+    fn handle_diff(&self, diff: Diff) {
+        self.diffs.borrow_mut().push(diff);
+    }
+
+    fn handle_future(&self, _: LocalBoxFuture<'static, ()>) {}
+
+    fn schedule_render(&self) {
+        self.run();
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct State(pub isize);
+
+/// App which adds action to its count, and renders the count as text
+#[derive(Clone, Default)]
+pub struct CounterApp;
+
+impl App for CounterApp {
+    type State = State;
+    type Action = isize;
+
+    fn reducer(&self, state: State, action: isize) -> (State, Task<isize>) {
+        (State(state.0 + action), Task::empty())
+    }
+
+    fn view(&self, state: State) -> View<isize> {
+        state.0.to_string().into()
+    }
+}
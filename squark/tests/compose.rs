@@ -0,0 +1,90 @@
+use serde_json::json;
+use squark::{handler, App, Child, Runtime, Task, TestRuntime, View};
+
+#[derive(Clone, Default)]
+struct Counter;
+
+impl App for Counter {
+    type State = isize;
+    type Action = isize;
+
+    fn reducer(&self, state: isize, action: isize) -> (isize, Task<isize>) {
+        let mut task = Task::empty();
+        if action == 0 {
+            task.push(async { 10 });
+        }
+        (state + action, task)
+    }
+
+    fn view(&self, state: isize) -> View<isize> {
+        View::new(
+            "button".to_owned(),
+            vec![],
+            vec![("click".to_owned(), handler(|_| Some(1)))],
+            vec![state.to_string().into()],
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    left: isize,
+    right: isize,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    Left(isize),
+    Right(isize),
+}
+
+#[derive(Clone, Default)]
+struct Pair {
+    counter: Counter,
+}
+
+impl App for Pair {
+    type State = State;
+    type Action = Action;
+
+    fn reducer(&self, mut state: State, action: Action) -> (State, Task<Action>) {
+        match action {
+            Action::Left(a) => {
+                let (left, task) = self.counter.reducer(state.left, a);
+                state.left = left;
+                (state, task.map(Action::Left))
+            }
+            Action::Right(a) => {
+                let (right, task) = self.counter.reducer(state.right, a);
+                state.right = right;
+                (state, task.map(Action::Right))
+            }
+        }
+    }
+
+    fn view(&self, state: State) -> View<Action> {
+        View::new(
+            "div".to_owned(),
+            vec![],
+            vec![],
+            vec![
+                Child::from(self.counter.view(state.left).map(Action::Left)),
+                Child::from(self.counter.view(state.right).map(Action::Right)),
+            ],
+        )
+    }
+}
+
+#[test]
+fn embed_child_app() {
+    let runtime = TestRuntime::<Pair>::new(State { left: 0, right: 0 });
+    runtime.run();
+
+    runtime.fire("button", "click", json!(null));
+    assert_eq!(runtime.state(), State { left: 1, right: 0 });
+
+    runtime.on_action(Action::Right(0));
+    runtime.run_until_stalled();
+    assert_eq!(runtime.state(), State { left: 1, right: 10 });
+    assert_eq!(runtime.root().text(), "110");
+}
@@ -0,0 +1,288 @@
+use quickcheck::{quickcheck, Gen, StdGen};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use squark::{AttributeValue, Diff, Element, Node};
+
+const NAMES: &[&str] = &["div", "span", "p"];
+const KEYS: &[&str] = &["a", "b", "c", "d", "e", "f", "g", "h"];
+const TEXTS: &[&str] = &["", "foo", "bar"];
+const ATTRIBUTES: &[&str] = &["id", "class", "title", "prop:value"];
+const EVENTS: &[&str] = &["click", "input"];
+const CLASSES: &[&str] = &["x", "y", "z"];
+const STYLES: &[&str] = &["color", "width"];
+
+fn gen_attributes<G: Gen>(g: &mut G) -> Vec<(String, AttributeValue)> {
+    let mut attributes = vec![];
+    for name in ATTRIBUTES {
+        if g.gen() {
+            attributes.push((name.to_string(), (*TEXTS.choose(g).unwrap()).into()));
+        }
+    }
+    if g.gen() {
+        attributes.push(("hidden".to_owned(), g.gen::<bool>().into()));
+    }
+    if g.gen() {
+        attributes.push(("tabindex".to_owned(), (g.gen_range(0, 3) as f64).into()));
+    }
+    if g.gen() && attributes.iter().all(|(k, _)| k != "class") {
+        let classes = CLASSES.iter().filter(|_| g.gen());
+        attributes.push(("class".to_owned(), AttributeValue::class_list(classes.cloned())));
+    }
+    if g.gen() {
+        let mut style = vec![];
+        for property in STYLES {
+            if g.gen() {
+                style.push((*property, *TEXTS.choose(g).unwrap()));
+            }
+        }
+        attributes.push(("style".to_owned(), style.into()));
+    }
+    if g.gen() {
+        attributes.push(("lang".to_owned(), AttributeValue::None));
+    }
+    attributes
+}
+
+fn gen_handlers<G: Gen>(g: &mut G) -> Vec<(String, String)> {
+    let mut handlers = vec![];
+    for name in EVENTS {
+        if g.gen() {
+            handlers.push((name.to_string(), format!("{}-{}", name, g.gen_range(0, 2))));
+        }
+    }
+    handlers
+}
+
+fn gen_node<G: Gen>(g: &mut G, depth: usize) -> Node {
+    match g.gen_range(0, 12) {
+        0 => Node::Null,
+        11 => Node::RawHtml(format!("<b>{}</b>", TEXTS.choose(g).unwrap())),
+        1 | 2 => Node::Text(TEXTS.choose(g).unwrap().to_string()),
+        3 if depth > 0 => {
+            let len = g.gen_range(0, 4);
+            Node::Fragment((0..len).map(|_| gen_node(g, depth - 1)).collect())
+        }
+        _ => {
+            let mut attributes = gen_attributes(g);
+            if g.gen() {
+                attributes.push(("key".to_owned(), (*KEYS.choose(g).unwrap()).into()));
+            }
+            let children = if depth == 0 {
+                vec![]
+            } else {
+                let len = g.gen_range(0, g.size().min(8) + 1);
+                (0..len).map(|_| gen_node(g, depth - 1)).collect()
+            };
+            Node::Element(Element::new(
+                NAMES.choose(g).unwrap().to_string(),
+                attributes,
+                gen_handlers(g),
+                children,
+            ))
+        }
+    }
+}
+
+/// Make tree similar to `node`, by reordering, dropping, adding and changing some of its descendants
+fn mutate<G: Gen>(g: &mut G, node: &Node, depth: usize) -> Node {
+    let el = match node {
+        Node::Element(el) if g.gen_range(0, 8) != 0 => el,
+        _ => return gen_node(g, depth),
+    };
+
+    let mut children = vec![];
+    for child in el.children() {
+        match g.gen_range(0, 6) {
+            0 => (),
+            1 | 2 => children.push(mutate(g, child, depth.saturating_sub(1))),
+            _ => children.push(child.to_owned()),
+        }
+    }
+    if g.gen() {
+        children.shuffle(g);
+    }
+    for _ in 0..g.gen_range(0, 3) {
+        let i = g.gen_range(0, children.len() + 1);
+        children.insert(i, gen_node(g, depth.saturating_sub(1)));
+    }
+
+    let mut attributes = el.attributes().to_vec();
+    if g.gen_range(0, 3) == 0 {
+        attributes.retain(|(k, _)| k == "key");
+        attributes.extend(gen_attributes(g));
+    }
+    let handlers = match g.gen_range(0, 3) {
+        0 => gen_handlers(g),
+        _ => el.handlers().to_vec(),
+    };
+    Node::Element(Element::new(el.name().to_owned(), attributes, handlers, children))
+}
+
+/// `Node` is not `Send`, so trees are generated from seed given by quickcheck
+fn gen(seed: u64) -> StdGen<StdRng> {
+    StdGen::new(StdRng::seed_from_u64(seed), 8)
+}
+
+/// Flatten fragments, drop `Node::Null` and absent attributes,
+/// and sort attributes and handlers, whose order is not kept by diff
+fn normalize(node: &Node) -> Node {
+    match node {
+        Node::Element(el) => {
+            let mut attributes = el.attributes().to_vec();
+            attributes.retain(|(_, v)| v != &AttributeValue::None);
+            attributes.sort_by(|a, b| a.0.cmp(&b.0));
+            let mut handlers = el.handlers().to_vec();
+            handlers.sort();
+            let children = Node::flatten(el.children()).into_iter().map(normalize).collect();
+            Node::Element(Element::new(el.name().to_owned(), attributes, handlers, children))
+        }
+        _ => node.to_owned(),
+    }
+}
+
+fn root(node: &Node) -> Node {
+    Node::Element(Element::new("body".to_owned(), vec![], vec![], vec![node.to_owned()]))
+}
+
+fn round_trip(a: &Node, b: &Node) -> bool {
+    let mut rendered = root(a);
+    for diff in Node::diff(&mut a.to_owned(), b) {
+        rendered.apply(&diff);
+    }
+    normalize(&rendered) == normalize(&root(b))
+}
+
+quickcheck! {
+    fn apply_diff_of_random_trees(seed: u64) -> bool {
+        let mut g = gen(seed);
+        let a = gen_node(&mut g, 3);
+        let b = gen_node(&mut g, 3);
+        round_trip(&a, &b)
+    }
+
+    fn apply_diff_of_similar_trees(seed: u64) -> bool {
+        let mut g = gen(seed);
+        let a = gen_node(&mut g, 3);
+        let b = mutate(&mut g, &a, 3);
+        round_trip(&a, &b)
+    }
+
+    fn diff_of_same_tree_is_empty(seed: u64) -> bool {
+        let a = gen_node(&mut gen(seed), 3);
+        Node::diff(&mut a.to_owned(), &a).is_empty()
+    }
+}
+
+fn element(attributes: Vec<(&str, AttributeValue)>) -> Node {
+    let attributes = attributes.into_iter().map(|(k, v)| (k.to_owned(), v)).collect();
+    Node::Element(Element::new("div".to_owned(), attributes, vec![], vec![]))
+}
+
+#[test]
+fn diff_class_list_and_style() {
+    let mut a = element(vec![
+        ("class", vec!["a", "b"].into()),
+        ("style", vec![("color", "red"), ("width", "1px")].into()),
+        ("title", "foo".into()),
+    ]);
+    let b = element(vec![
+        ("class", vec!["b", "c"].into()),
+        ("style", vec![("color", "blue")].into()),
+        ("title", None::<String>.into()),
+        ("lang", None::<String>.into()),
+    ]);
+    let diffs = Node::diff(&mut a, &b);
+    match diffs.as_slice() {
+        [Diff::PatchChild(0, diffs)] => match diffs.as_slice() {
+            [Diff::RemoveClass(removed), Diff::AddClass(added), Diff::RemoveStyle(property), Diff::SetStyle(color, blue), Diff::RemoveAttribute(title)] =>
+            {
+                assert_eq!((removed.as_str(), added.as_str()), ("a", "c"));
+                assert_eq!(property, "width");
+                assert_eq!((color.as_str(), blue.as_str()), ("color", "blue"));
+                assert_eq!(title, "title");
+            }
+            diffs => panic!("unexpected diffs: {:?}", diffs),
+        },
+        diffs => panic!("unexpected diffs: {:?}", diffs),
+    }
+}
+
+#[test]
+fn drop_empty_and_split_classes() {
+    assert_eq!(
+        AttributeValue::from(vec!["a b", "", " c "]),
+        AttributeValue::class_list(vec!["a", "b", "c"])
+    );
+    let mut a = element(vec![("class", vec!["a"].into())]);
+    let b = element(vec![("class", vec!["a", ""].into())]);
+    assert!(Node::diff(&mut a, &b).is_empty());
+}
+
+#[test]
+fn diff_property() {
+    let mut a = element(vec![("prop:value", "foo".into()), ("prop:checked", true.into())]);
+    let b = element(vec![("prop:value", "bar".into())]);
+    let diffs = Node::diff(&mut a, &b);
+    match diffs.as_slice() {
+        [Diff::PatchChild(0, diffs)] => match diffs.as_slice() {
+            [Diff::SetProperty(value, AttributeValue::String(bar)), Diff::RemoveProperty(checked)] => {
+                assert_eq!((value.as_str(), bar.as_str()), ("value", "bar"));
+                assert_eq!(checked, "checked");
+            }
+            diffs => panic!("unexpected diffs: {:?}", diffs),
+        },
+        diffs => panic!("unexpected diffs: {:?}", diffs),
+    }
+}
+
+fn parent(children: Vec<Node>, attributes: Vec<(&str, AttributeValue)>) -> Node {
+    let attributes = attributes.into_iter().map(|(k, v)| (k.to_owned(), v)).collect();
+    Node::Element(Element::new("div".to_owned(), attributes, vec![], children))
+}
+
+#[test]
+fn diff_raw_html() {
+    let raw = |html: &str| parent(vec![Node::RawHtml(html.to_owned())], vec![]);
+    let text = parent(vec![Node::Text("a".to_owned())], vec![]);
+
+    let patches = |a: &Node, b: &Node| match Node::diff(&mut a.to_owned(), b).as_slice() {
+        [Diff::PatchChild(0, diffs)] => diffs.to_owned(),
+        [] => vec![],
+        diffs => panic!("unexpected diffs: {:?}", diffs),
+    };
+    match patches(&raw("<b>a</b>"), &raw("<i>a</i>")).as_slice() {
+        [Diff::SetInnerHtml(html)] => assert_eq!(html, "<i>a</i>"),
+        diffs => panic!("unexpected diffs: {:?}", diffs),
+    }
+    match patches(&text, &raw("<b>a</b>")).as_slice() {
+        [Diff::SetInnerHtml(html)] => assert_eq!(html, "<b>a</b>"),
+        diffs => panic!("unexpected diffs: {:?}", diffs),
+    }
+    match patches(&raw("<b>a</b>"), &text).as_slice() {
+        [Diff::SetInnerHtml(html), Diff::AddChild(0, Node::Text(a))] => {
+            assert_eq!((html.as_str(), a.as_str()), ("", "a"));
+        }
+        diffs => panic!("unexpected diffs: {:?}", diffs),
+    }
+    assert!(round_trip(&raw("<b>a</b>"), &text));
+    assert!(round_trip(&text, &raw("<b>a</b>")));
+}
+
+#[test]
+fn skip_children_of_unmanaged_element() {
+    let unmanaged = |child: &str, id: &str| {
+        parent(
+            vec![Node::Text(child.to_owned())],
+            vec![("unmanaged", true.into()), ("id", id.into())],
+        )
+    };
+    let mut a = unmanaged("a", "x");
+    match Node::diff(&mut a, &unmanaged("b", "y")).as_slice() {
+        [Diff::PatchChild(0, diffs)] => match diffs.as_slice() {
+            [Diff::SetAttribute(id, _)] => assert_eq!(id, "id"),
+            diffs => panic!("unexpected diffs: {:?}", diffs),
+        },
+        diffs => panic!("unexpected diffs: {:?}", diffs),
+    }
+}
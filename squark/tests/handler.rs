@@ -0,0 +1,159 @@
+use serde_json::json;
+use squark::{handler, listener_options, App, Child, Diff, Node, Runtime, Task, TestRuntime, View};
+
+mod common;
+use common::RecordingRuntime;
+
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    count: isize,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    Add(isize),
+}
+
+#[derive(Clone, Default)]
+struct CounterApp;
+
+impl App for CounterApp {
+    type State = State;
+    type Action = Action;
+
+    fn reducer(&self, mut state: State, action: Action) -> (State, Task<Action>) {
+        match action {
+            Action::Add(n) => state.count += n,
+        };
+        (state, Task::empty())
+    }
+
+    fn view(&self, state: State) -> View<Action> {
+        let count = state.count;
+        View::new(
+            "div".to_owned(),
+            vec![],
+            vec![],
+            vec![
+                Child::from(View::new(
+                    "span".to_owned(),
+                    vec![],
+                    vec![],
+                    vec![count.to_string().into()],
+                )),
+                Child::from(View::new(
+                    "button".to_owned(),
+                    vec![],
+                    vec![("click".to_owned(), handler(move |_| Some(Action::Add(count + 1))))],
+                    vec![],
+                )),
+            ],
+        )
+    }
+}
+
+fn collect_node_handler_ids(node: &Node, ids: &mut Vec<String>) {
+    if let Node::Element(el) = node {
+        ids.extend(el.handlers().iter().map(|(_, id)| id.to_owned()));
+        for child in el.children() {
+            collect_node_handler_ids(child, ids);
+        }
+    }
+}
+
+fn collect_handler_ids(diffs: &[Diff], ids: &mut Vec<String>) {
+    for diff in diffs {
+        match diff {
+            Diff::SetHandler(_, id) => ids.push(id.to_owned()),
+            Diff::PatchChild(_, diffs) => collect_handler_ids(diffs, ids),
+            Diff::AddChild(_, node) | Diff::ReplaceChild(_, node) => {
+                collect_node_handler_ids(node, ids)
+            }
+            _ => (),
+        }
+    }
+}
+
+#[test]
+fn keep_handler_id_across_renders() {
+    let runtime = RecordingRuntime::<CounterApp>::new(State { count: 0 });
+    runtime.run();
+    let diffs = runtime.diffs.replace(vec![]);
+    let mut ids = vec![];
+    collect_handler_ids(&diffs, &mut ids);
+    assert_eq!(ids.len(), 1);
+
+    runtime.listener(&ids[0])(json!(null));
+    runtime.listener(&ids[0])(json!(null));
+    let mut ids = vec![];
+    collect_handler_ids(&runtime.diffs.borrow(), &mut ids);
+    assert!(ids.is_empty());
+}
+
+#[test]
+fn call_handler_of_latest_view() {
+    let runtime = TestRuntime::<CounterApp>::new(State { count: 0 });
+    runtime.run();
+
+    runtime.fire("button", "click", json!(null));
+    runtime.fire("button", "click", json!(null));
+    assert_eq!(runtime.state(), State { count: 3 });
+}
+
+#[derive(Clone, Default)]
+struct KeyedApp;
+
+fn clickable(attributes: Vec<(&str, &str)>, n: isize, children: Vec<Child<Action>>) -> View<Action> {
+    let attributes = attributes.into_iter().map(|(k, v)| (k.to_owned(), v.into())).collect();
+    let handlers = vec![("click".to_owned(), handler(move |_| Some(Action::Add(n))))];
+    View::new("li".to_owned(), attributes, handlers, children)
+}
+
+impl App for KeyedApp {
+    type State = State;
+    type Action = Action;
+
+    fn reducer(&self, state: State, action: Action) -> (State, Task<Action>) {
+        CounterApp.reducer(state, action)
+    }
+
+    fn view(&self, _: State) -> View<Action> {
+        let nested = clickable(vec![("id", "nested")], 100, vec![]);
+        View::new(
+            "ul".to_owned(),
+            vec![],
+            vec![],
+            vec![
+                clickable(vec![("key", "a"), ("id", "first")], 1, vec![nested.into()]).into(),
+                clickable(vec![("key", "a"), ("id", "second")], 10, vec![]).into(),
+                clickable(vec![("key", "a>0"), ("id", "third")], 1000, vec![]).into(),
+            ],
+        )
+    }
+}
+
+#[test]
+fn call_handler_of_each_duplicate_keyed_sibling() {
+    let runtime = TestRuntime::<KeyedApp>::new(State { count: 0 });
+    runtime.run();
+
+    runtime.fire("#first", "click", json!(null));
+    assert_eq!(runtime.state(), State { count: 1 });
+    runtime.fire("#second", "click", json!(null));
+    assert_eq!(runtime.state(), State { count: 11 });
+    runtime.fire("#nested", "click", json!(null));
+    assert_eq!(runtime.state(), State { count: 111 });
+    runtime.fire("#third", "click", json!(null));
+    assert_eq!(runtime.state(), State { count: 1111 });
+}
+
+#[test]
+fn drop_passive_with_prevent() {
+    let (event, options) = listener_options("touchstart|passive|prevent");
+    assert_eq!(event, "touchstart");
+    assert!(options.prevent);
+    assert!(!options.passive);
+
+    let (_, options) = listener_options("touchstart|passive");
+    assert!(options.passive);
+}
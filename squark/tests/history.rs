@@ -0,0 +1,39 @@
+use squark::{Runtime, TestRuntime, TimeTravel};
+
+mod common;
+use common::{CounterApp, State};
+
+#[test]
+fn jump_pause_and_resume() {
+    let runtime = TestRuntime::<CounterApp>::new(State(0));
+    runtime.run();
+    runtime.enable_history();
+    runtime.on_action(1);
+    runtime.on_action(2);
+    runtime.on_action(3);
+    assert_eq!(runtime.history().len(), 3);
+    assert_eq!(runtime.history()[1], (2, State(3)));
+
+    runtime.jump_to(1);
+    assert_eq!(runtime.state(), State(1));
+    assert_eq!(runtime.root().text(), "1");
+
+    runtime.on_action(10);
+    assert_eq!(runtime.state(), State(1));
+
+    runtime.resume();
+    assert_eq!(runtime.state(), State(16));
+    assert_eq!(runtime.history_position(), Some(4));
+}
+
+#[test]
+fn replay() {
+    let runtime = TestRuntime::<CounterApp>::new(State(10));
+    runtime.run();
+    runtime.enable_history();
+    runtime.on_action(1);
+    runtime.jump_to(0);
+    runtime.replay();
+    assert_eq!(runtime.state(), State(10));
+    assert_eq!(runtime.history(), vec![(1, State(11))]);
+}
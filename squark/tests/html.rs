@@ -0,0 +1,187 @@
+use std::iter::FromIterator;
+use squark::{handler, AttributeValue, Child, View};
+
+fn v() -> View<()> {
+    View::new(
+        "ul".to_owned(),
+        vec![
+            ("class".to_owned(), "a&b \"c\"".into()),
+            ("hidden".to_owned(), false.into()),
+            ("draggable".to_owned(), true.into()),
+        ],
+        vec![("click".to_owned(), handler(|_| Some(())))],
+        vec![
+            "<li>".into(),
+            "".into(),
+            ().into(),
+            View::new("br".to_owned(), vec![], vec![], vec![]).into(),
+            Child::from_iter(vec!["x".into(), "y".into()]),
+        ],
+    )
+}
+
+#[test]
+fn render_to_string() {
+    assert_eq!(
+        v().to_html(),
+        "<ul class=\"a&amp;b &quot;c&quot;\" draggable=\"true\">\
+         &lt;li&gt;<!--squark-text--><br>x<!--squark-text-->y</ul>"
+    );
+}
+
+#[test]
+fn render_to_io() {
+    let mut buf = vec![];
+    v().write_html_io(&mut buf).unwrap();
+    assert_eq!(String::from_utf8(buf).unwrap(), v().to_html());
+}
+
+#[test]
+fn render_fragment() {
+    let v: View<()> = View::new(
+        "p".to_owned(),
+        vec![],
+        vec![],
+        vec![
+            "a".into(),
+            View::fragment(vec!["b".into(), View::fragment(vec![]).into()]).into(),
+            View::new("br".to_owned(), vec![], vec![], vec![]).into(),
+        ],
+    );
+    assert_eq!(v.to_html(), "<p>a<!--squark-text-->b<br></p>");
+}
+
+#[test]
+fn render_structured_attributes() {
+    let v: View<()> = View::new(
+        "div".to_owned(),
+        vec![
+            ("class".to_owned(), vec!["b", "a"].into()),
+            ("style".to_owned(), vec![("width", "10px"), ("color", "red")].into()),
+            ("tabindex".to_owned(), 1.into()),
+            ("title".to_owned(), None::<String>.into()),
+        ],
+        vec![],
+        vec![],
+    );
+    assert_eq!(
+        v.to_html(),
+        "<div class=\"a b\" style=\"color: red; width: 10px;\" tabindex=\"1\"></div>"
+    );
+}
+
+#[test]
+fn render_svg() {
+    let v: View<()> = View::new(
+        "svg".to_owned(),
+        vec![],
+        vec![],
+        vec![
+            View::new(
+                "image".to_owned(),
+                vec![("xlink:href".to_owned(), "a.png".into())],
+                vec![],
+                vec![],
+            )
+            .into(),
+            View::new("style".to_owned(), vec![], vec![], vec!["a > b".into()]).into(),
+        ],
+    );
+    assert_eq!(
+        v.to_html(),
+        "<svg><image xlink:href=\"a.png\"></image><style>a &gt; b</style></svg>"
+    );
+}
+
+#[test]
+fn render_raw_html() {
+    let v: View<()> = View::new(
+        "div".to_owned(),
+        vec![],
+        vec![],
+        vec![
+            View::new(
+                "p".to_owned(),
+                vec![],
+                vec![],
+                vec![View::raw_html("<b>a</b>".to_owned()).into()],
+            )
+            .into(),
+            View::new(
+                "p".to_owned(),
+                vec![],
+                vec![],
+                vec!["a".into(), View::raw_html("<b>b</b>".to_owned()).into()],
+            )
+            .into(),
+        ],
+    );
+    assert_eq!(
+        v.to_html(),
+        "<div><p><b>a</b></p><p>a<!--squark-text-->&lt;b&gt;b&lt;/b&gt;</p></div>"
+    );
+}
+
+#[test]
+fn escape_end_tag_in_raw_text() {
+    let script = |child: Child<()>| View::new("script".to_owned(), vec![], vec![], vec![child]);
+    let text = "if (a < b) {}</script><img src=x onerror=alert(1)>";
+    assert_eq!(
+        script(text.into()).to_html(),
+        "<script>if (a < b) {}<\\/script><img src=x onerror=alert(1)></script>"
+    );
+    assert_eq!(
+        script(View::raw_html("</b>".to_owned()).into()).to_html(),
+        "<script></b></script>"
+    );
+    let split = vec!["a <".into(), "/SCRIPT>".into()];
+    assert_eq!(
+        View::<()>::new("script".to_owned(), vec![], vec![], split).to_html(),
+        "<script>a <\\/SCRIPT></script>"
+    );
+
+    let css = "p::after { content: \"</p>\" }</style>";
+    assert_eq!(
+        View::<()>::new("style".to_owned(), vec![], vec![], vec![css.into()]).to_html(),
+        "<style>p::after { content: \"</p>\" }<\\/style></style>"
+    );
+}
+
+#[test]
+fn render_escapable_raw_text_without_marker() {
+    let children = vec!["a < b".into(), "".into(), "c".into()];
+    let v: View<()> = View::new("textarea".to_owned(), vec![], vec![], children);
+    assert_eq!(v.to_html(), "<textarea>a &lt; bc</textarea>");
+}
+
+#[test]
+fn render_reflected_properties_only() {
+    let attributes = vec![
+        ("prop:value".to_owned(), "v".into()),
+        ("prop:checked".to_owned(), true.into()),
+        ("prop:selected".to_owned(), false.into()),
+        ("prop:innerHTML".to_owned(), "<b>x</b>".into()),
+        ("prop:indeterminate".to_owned(), true.into()),
+    ];
+    let v: View<()> = View::new("input".to_owned(), attributes, vec![], vec![]);
+    assert_eq!(v.to_html(), "<input value=\"v\" checked>");
+}
+
+#[test]
+fn render_boolean_and_enumerated_attributes() {
+    let attributes = vec![
+        ("aria-hidden", false),
+        ("spellcheck", false),
+        ("draggable", true),
+        ("hidden", false),
+        ("disabled", true),
+    ];
+    let attributes = attributes.into_iter().map(|(k, v)| (k.to_owned(), v.into())).collect();
+    let v: View<()> = View::new("button".to_owned(), attributes, vec![], vec![]);
+    assert_eq!(
+        v.to_html(),
+        "<button aria-hidden=\"false\" spellcheck=\"false\" draggable=\"true\" disabled></button>"
+    );
+    assert_eq!(AttributeValue::Bool(false).to_attribute_string("aria-expanded"), Some("false".to_owned()));
+    assert_eq!(AttributeValue::Bool(false).to_attribute_string("checked"), None);
+}
@@ -0,0 +1,90 @@
+use squark::{App, Child, Diff, Runtime, Task, TestRuntime, View};
+
+mod common;
+use common::RecordingRuntime;
+
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    items: Vec<&'static str>,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    Set(Vec<&'static str>),
+}
+
+#[derive(Clone, Default)]
+struct ListApp;
+
+impl App for ListApp {
+    type State = State;
+    type Action = Action;
+
+    fn reducer(&self, _: State, action: Action) -> (State, Task<Action>) {
+        match action {
+            Action::Set(items) => (State { items }, Task::empty()),
+        }
+    }
+
+    fn view(&self, state: State) -> View<Action> {
+        View::new(
+            "ul".to_owned(),
+            vec![],
+            vec![],
+            state
+                .items
+                .iter()
+                .map(|item| {
+                    Child::from(View::new(
+                        "li".to_owned(),
+                        vec![("key".to_owned(), item.to_string().into())],
+                        vec![],
+                        vec![item.to_string().into()],
+                    ))
+                })
+                .collect(),
+        )
+    }
+}
+
+fn texts(runtime: &TestRuntime<ListApp>) -> Vec<String> {
+    runtime.find_all("li").iter().map(|li| li.text()).collect()
+}
+
+#[test]
+fn move_keyed_children() {
+    let runtime = RecordingRuntime::<ListApp>::new(State {
+        items: vec!["a", "b", "c", "d", "e"],
+    });
+    runtime.run();
+    runtime.diffs.borrow_mut().clear();
+
+    runtime.on_action(Action::Set(vec!["b", "c", "d", "e", "a"]));
+    let diffs = runtime.diffs.replace(vec![]);
+    match diffs.as_slice() {
+        [Diff::PatchChild(0, diffs)] => match diffs.as_slice() {
+            [Diff::MoveChild(0, 4)] => (),
+            diffs => panic!("unexpected diffs: {:?}", diffs),
+        },
+        diffs => panic!("unexpected diffs: {:?}", diffs),
+    }
+}
+
+#[test]
+fn reorder_keyed_children() {
+    let runtime = TestRuntime::<ListApp>::new(State { items: vec!["a", "b", "c", "d", "e"] });
+    runtime.run();
+
+    let orders = vec![
+        vec!["e", "d", "c", "b", "a"],
+        vec!["d", "x", "a", "e"],
+        vec!["a", "b", "c", "d", "e", "f"],
+        vec!["c", "a", "f", "b"],
+        vec![],
+        vec!["b", "a"],
+    ];
+    for items in orders {
+        runtime.on_action(Action::Set(items.to_owned()));
+        assert_eq!(texts(&runtime), items);
+    }
+}
@@ -0,0 +1,125 @@
+use std::cell::Cell;
+use serde_json::json;
+use squark::{handler, App, Child, Runtime, Task, TestRuntime, View};
+
+thread_local! {
+    static RENDERED: Cell<usize> = Cell::new(0);
+}
+
+fn rendered() -> usize {
+    RENDERED.with(|r| r.replace(0))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Item {
+    id: u32,
+    count: u32,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    items: Vec<Item>,
+    title: String,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    Increment(u32),
+    Reverse,
+    SetTitle(String),
+}
+
+#[derive(Clone, Default)]
+struct ListApp;
+
+fn row(item: &Item) -> View<Action> {
+    RENDERED.with(|r| r.set(r.get() + 1));
+    let id = item.id;
+    View::new(
+        "li".to_owned(),
+        vec![
+            ("key".to_owned(), id.to_string().into()),
+            ("id".to_owned(), format!("item-{}", id).into()),
+        ],
+        vec![("click".to_owned(), handler(move |_| Some(Action::Increment(id))))],
+        vec![item.count.to_string().into()],
+    )
+}
+
+impl App for ListApp {
+    type State = State;
+    type Action = Action;
+
+    fn reducer(&self, mut state: State, action: Action) -> (State, Task<Action>) {
+        match action {
+            Action::Increment(id) => {
+                for item in state.items.iter_mut().filter(|item| item.id == id) {
+                    item.count += 1;
+                }
+            }
+            Action::Reverse => state.items.reverse(),
+            Action::SetTitle(title) => state.title = title,
+        }
+        (state, Task::empty())
+    }
+
+    fn view(&self, state: State) -> View<Action> {
+        View::new(
+            "div".to_owned(),
+            vec![],
+            vec![],
+            vec![
+                Child::from(View::new("h1".to_owned(), vec![], vec![], vec![state.title.into()])),
+                Child::from(View::new(
+                    "ul".to_owned(),
+                    vec![],
+                    vec![],
+                    vec![state.items.into_iter().map(|item| View::lazy(item, row)).collect()],
+                )),
+            ],
+        )
+    }
+}
+
+fn initial_state() -> State {
+    State {
+        items: (0..3).map(|id| Item { id, count: 0 }).collect(),
+        title: "list".to_owned(),
+    }
+}
+
+fn texts(runtime: &TestRuntime<ListApp>) -> Vec<String> {
+    runtime.find_all("li").iter().map(|li| li.text()).collect()
+}
+
+#[test]
+fn reuse_unchanged_subtree() {
+    let runtime = TestRuntime::<ListApp>::new(initial_state());
+    runtime.run();
+    assert_eq!(rendered(), 3);
+
+    runtime.on_action(Action::SetTitle("todo".to_owned()));
+    assert_eq!(runtime.find("h1").unwrap().text(), "todo");
+    assert_eq!(rendered(), 0);
+
+    runtime.fire("#item-1", "click", json!(null));
+    runtime.fire("#item-1", "click", json!(null));
+    assert_eq!(texts(&runtime), vec!["0", "2", "0"]);
+    assert_eq!(rendered(), 2);
+
+    runtime.on_action(Action::Reverse);
+    assert_eq!(texts(&runtime), vec!["0", "2", "0"]);
+    assert_eq!(rendered(), 0);
+
+    // Handlers of reused subtrees are still called after moved
+    runtime.fire("#item-0", "click", json!(null));
+    assert_eq!(runtime.find("li").unwrap().text(), "0");
+    assert_eq!(runtime.find("#item-0").unwrap().text(), "1");
+    assert_eq!(rendered(), 1);
+}
+
+#[test]
+fn render_lazy_to_html() {
+    let view: View<Action> = View::lazy(Item { id: 7, count: 3 }, row);
+    assert_eq!(view.to_html(), "<li key=\"7\" id=\"item-7\">3</li>");
+}
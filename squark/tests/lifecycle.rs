@@ -0,0 +1,57 @@
+use squark::{App, Child, Runtime, Task, TestRuntime, View};
+
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    items: Vec<u32>,
+    log: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    Set(Vec<u32>),
+    Log(String),
+}
+
+#[derive(Clone, Default)]
+struct ListApp;
+
+impl App for ListApp {
+    type State = State;
+    type Action = Action;
+
+    fn reducer(&self, mut state: State, action: Action) -> (State, Task<Action>) {
+        match action {
+            Action::Set(items) => state.items = items,
+            Action::Log(entry) => state.log.push(entry),
+        }
+        (state, Task::empty())
+    }
+
+    fn view(&self, state: State) -> View<Action> {
+        let items = state.items.iter().map(|&item| {
+            View::new(
+                "li".to_owned(),
+                vec![("key".to_owned(), item.into())],
+                vec![],
+                vec![item.to_string().into()],
+            )
+            .on_mount(move |_| Some(Action::Log(format!("mount {}", item))))
+            .on_unmount(move |_| Some(Action::Log(format!("unmount {}", item))))
+        });
+        View::new("ul".to_owned(), vec![], vec![], vec![items.collect::<Child<Action>>()])
+            .on_update(|_| Some(Action::Log("update".to_owned())))
+    }
+}
+
+#[test]
+fn call_lifecycle_hooks() {
+    let runtime = TestRuntime::<ListApp>::new(State { items: vec![1, 2], log: vec![] });
+    runtime.run();
+    assert_eq!(runtime.state().log, vec!["mount 1", "mount 2"]);
+
+    runtime.on_action(Action::Set(vec![2, 3]));
+    assert_eq!(runtime.state().log[2..].to_vec(), vec!["unmount 1", "mount 3", "update"]);
+
+    runtime.on_action(Action::Set(vec![3, 2]));
+    assert_eq!(runtime.state().log[5..].to_vec(), vec!["update"]);
+}
@@ -0,0 +1,62 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use squark::{Middleware, Runtime, Task, TestRuntime};
+
+mod common;
+use common::{CounterApp, State};
+
+struct Logger(Rc<RefCell<Vec<String>>>);
+
+impl Middleware<CounterApp> for Logger {
+    fn after(&self, action: &isize, old_state: &State, new_state: &State, _: &mut Task<isize>) {
+        self.0
+            .borrow_mut()
+            .push(format!("{} {:?} -> {:?}", action, old_state, new_state));
+    }
+}
+
+struct DropNegative;
+
+impl Middleware<CounterApp> for DropNegative {
+    fn before(&self, action: isize, _: &mut Task<isize>) -> Option<isize> {
+        if action < 0 {
+            return None;
+        }
+        Some(action)
+    }
+}
+
+struct Bonus;
+
+impl Middleware<CounterApp> for Bonus {
+    fn after(&self, action: &isize, _: &State, _: &State, task: &mut Task<isize>) {
+        if *action == 100 {
+            task.push(async { 1 });
+        }
+    }
+}
+
+#[test]
+fn middlewares() {
+    let log = Rc::new(RefCell::new(vec![]));
+    let runtime = TestRuntime::<CounterApp>::new(State(0));
+    runtime.add_middleware(Logger(log.clone()));
+    runtime.add_middleware(DropNegative);
+    runtime.add_middleware(Bonus);
+    runtime.run();
+
+    runtime.on_action(1);
+    runtime.on_action(-5);
+    runtime.on_action(100);
+    runtime.run_until_stalled();
+
+    assert_eq!(runtime.state(), State(102));
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            "1 State(0) -> State(1)",
+            "100 State(1) -> State(101)",
+            "1 State(101) -> State(102)",
+        ]
+    );
+}
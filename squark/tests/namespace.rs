@@ -0,0 +1,58 @@
+use squark::{App, Child, Namespace, Runtime, Task, TestRuntime, View};
+
+#[derive(Clone, Debug, PartialEq)]
+struct State;
+
+#[derive(Clone, Default)]
+struct SvgApp;
+
+fn circle(r: &u32) -> View<()> {
+    View::new("circle".to_owned(), vec![("r".to_owned(), (*r).into())], vec![], vec![])
+}
+
+impl App for SvgApp {
+    type State = State;
+    type Action = ();
+
+    fn reducer(&self, state: State, _: ()) -> (State, Task<()>) {
+        (state, Task::empty())
+    }
+
+    fn view(&self, _: State) -> View<()> {
+        View::new(
+            "div".to_owned(),
+            vec![],
+            vec![],
+            vec![Child::from(View::new(
+                "svg".to_owned(),
+                vec![],
+                vec![],
+                vec![
+                    View::fragment(vec![View::lazy(3, circle).into()]).into(),
+                    View::new(
+                        "foreignObject".to_owned(),
+                        vec![],
+                        vec![],
+                        vec![View::new("p".to_owned(), vec![], vec![], vec![]).into()],
+                    )
+                    .into(),
+                    View::new("math".to_owned(), vec![], vec![], vec![]).into(),
+                ],
+            ))],
+        )
+    }
+}
+
+#[test]
+fn inherit_namespace() {
+    let runtime = TestRuntime::<SvgApp>::new(State);
+    runtime.run();
+
+    let namespace = |selector| runtime.find(selector).unwrap().namespace();
+    assert_eq!(namespace("div"), Namespace::Html);
+    assert_eq!(namespace("svg"), Namespace::Svg);
+    assert_eq!(namespace("circle"), Namespace::Svg);
+    assert_eq!(namespace("foreignObject"), Namespace::Svg);
+    assert_eq!(namespace("p"), Namespace::Html);
+    assert_eq!(namespace("math"), Namespace::MathMl);
+}
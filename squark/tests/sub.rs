@@ -0,0 +1,149 @@
+use std::cell::RefCell;
+use squark::{App, HandlerArg, Runtime, Sub, Task, TestRuntime, View};
+
+type Emit = Box<dyn Fn(HandlerArg)>;
+
+thread_local! {
+    static EMITTERS: RefCell<Vec<Emit>> = RefCell::new(vec![]);
+}
+
+/// Subscription keyed like `interval(1000, ..)` of `squark-web`, whose emitter is kept to be called by test
+fn tick(f: fn(HandlerArg) -> Option<String>) -> Sub<String> {
+    let subscriber = |emit: Emit| {
+        EMITTERS.with(|e| e.borrow_mut().push(emit));
+        Box::new(|| ()) as Box<dyn FnOnce()>
+    };
+    Sub::new("interval:1000".to_owned(), subscriber, f)
+}
+
+#[derive(Clone, Default)]
+struct TickApp;
+
+impl App for TickApp {
+    type State = Vec<String>;
+    type Action = String;
+
+    fn reducer(&self, mut state: Vec<String>, action: String) -> (Vec<String>, Task<String>) {
+        state.push(action);
+        (state, Task::empty())
+    }
+
+    fn view(&self, _: Vec<String>) -> View<String> {
+        View::null()
+    }
+
+    fn subscriptions(&self, _: &Vec<String>) -> Sub<String> {
+        Sub::batch(vec![
+            tick(|_| Some("a".to_owned())),
+            tick(|_| Some("b".to_owned())).map(|s| s.to_uppercase()),
+        ])
+    }
+}
+
+#[test]
+fn keep_subscriptions_with_same_key() {
+    let runtime = TestRuntime::<TickApp>::new(vec![]);
+    runtime.run();
+    assert_eq!(EMITTERS.with(|e| e.borrow().len()), 2);
+
+    EMITTERS.with(|e| {
+        for emit in e.borrow().iter() {
+            emit(HandlerArg::Null);
+        }
+    });
+    assert_eq!(runtime.state(), vec!["a", "B"]);
+    assert_eq!(EMITTERS.with(|e| e.borrow().len()), 2);
+}
+
+thread_local! {
+    static LOG: RefCell<Vec<String>> = RefCell::new(vec![]);
+    static WATCHERS: RefCell<Vec<(&'static str, Emit)>> = RefCell::new(vec![]);
+}
+
+/// Subscription which logs when it is started and stopped
+fn watch<F>(key: &'static str, f: F) -> Sub<Watch>
+where
+    F: Fn(HandlerArg) -> Option<Watch> + 'static,
+{
+    let subscriber = move |emit: Emit| {
+        LOG.with(|l| l.borrow_mut().push(format!("start {}", key)));
+        WATCHERS.with(|w| w.borrow_mut().push((key, emit)));
+        let unsubscribe = move || LOG.with(|l| l.borrow_mut().push(format!("stop {}", key)));
+        Box::new(unsubscribe) as Box<dyn FnOnce()>
+    };
+    Sub::new(key.to_owned(), subscriber, f)
+}
+
+fn emit(key: &str) {
+    WATCHERS.with(|w| {
+        for (k, emit) in w.borrow().iter() {
+            if *k == key {
+                emit(HandlerArg::Null);
+            }
+        }
+    });
+}
+
+#[derive(Clone, Debug)]
+enum Watch {
+    Set(Vec<&'static str>),
+    Fired(String),
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct WatchState {
+    keys: Vec<&'static str>,
+    fired: Vec<String>,
+}
+
+#[derive(Clone, Default)]
+struct WatchApp;
+
+impl App for WatchApp {
+    type State = WatchState;
+    type Action = Watch;
+
+    fn reducer(&self, mut state: WatchState, action: Watch) -> (WatchState, Task<Watch>) {
+        match action {
+            Watch::Set(keys) => state.keys = keys,
+            Watch::Fired(s) => state.fired.push(s),
+        }
+        (state, Task::empty())
+    }
+
+    fn view(&self, _: WatchState) -> View<Watch> {
+        View::null()
+    }
+
+    fn subscriptions(&self, state: &WatchState) -> Sub<Watch> {
+        let n = state.keys.len();
+        let subs = state
+            .keys
+            .iter()
+            .map(|&key| watch(key, move |_| Some(Watch::Fired(format!("{} of {}", key, n)))));
+        Sub::batch(subs.collect())
+    }
+}
+
+#[test]
+fn start_stop_and_keep_subscriptions() {
+    let runtime = TestRuntime::<WatchApp>::new(WatchState {
+        keys: vec!["a"],
+        fired: vec![],
+    });
+    runtime.run();
+    assert_eq!(LOG.with(|l| l.borrow().to_owned()), vec!["start a"]);
+
+    runtime.on_action(Watch::Set(vec!["a", "b"]));
+    assert_eq!(LOG.with(|l| l.borrow().to_owned()), vec!["start a", "start b"]);
+
+    // Kept subscription calls handler of latest state
+    emit("a");
+    assert_eq!(runtime.state().fired, vec!["a of 2"]);
+
+    runtime.on_action(Watch::Set(vec!["b"]));
+    assert_eq!(LOG.with(|l| l.borrow().to_owned()), vec!["start a", "start b", "stop a"]);
+
+    runtime.on_action(Watch::Set(vec![]));
+    assert_eq!(LOG.with(|l| l.borrow().to_owned()), vec!["start a", "start b", "stop a", "stop b"]);
+}
@@ -0,0 +1,171 @@
+use std::cell::RefCell;
+use futures::channel::oneshot;
+use futures::future;
+use squark::{App, Runtime, Task, TestRuntime, View};
+
+#[derive(Clone, Debug)]
+enum Action {
+    Push(String),
+    Run,
+    Cancel,
+    Focus,
+}
+
+thread_local! {
+    static RECEIVER: RefCell<Option<oneshot::Receiver<String>>> = RefCell::new(None);
+    static FOCUSED: RefCell<u32> = RefCell::new(0);
+}
+
+#[derive(Clone, Default)]
+struct LogApp;
+
+impl App for LogApp {
+    type State = Vec<String>;
+    type Action = Action;
+
+    fn reducer(&self, mut state: Vec<String>, action: Action) -> (Vec<String>, Task<Action>) {
+        match action {
+            Action::Push(s) => {
+                state.push(s);
+                (state, Task::empty())
+            }
+            Action::Run => {
+                let rx = RECEIVER.with(|r| r.borrow_mut().take().unwrap());
+                let task = Task::perform(
+                    rx,
+                    Action::Push,
+                    |_| Action::Push("canceled".to_owned()),
+                );
+                (state, task.with_key("waiting"))
+            }
+            Action::Cancel => (state, Task::cancel("waiting")),
+            Action::Focus => (state, Task::after_render(|| FOCUSED.with(|f| *f.borrow_mut() += 1))),
+        }
+    }
+
+    fn view(&self, _: Vec<String>) -> View<Action> {
+        View::null()
+    }
+}
+
+fn push(s: &str) -> Task<Action> {
+    Task::new(future::ready(Action::Push(s.to_owned())))
+}
+
+#[test]
+fn sequence_and_batch() {
+    let runtime = TestRuntime::<LogApp>::new(vec![]);
+    runtime.run();
+
+    let task = Task::sequence(vec![
+        Task::batch(vec![push("a"), push("b")]),
+        push("c").map(|a| match a {
+            Action::Push(s) => Action::Push(s.to_uppercase()),
+            a => a,
+        }),
+    ]);
+    runtime.run_with_task(task);
+    runtime.run_until_stalled();
+    assert_eq!(runtime.state(), vec!["a", "b", "C"]);
+}
+
+#[test]
+fn cancel_keyed_task() {
+    let runtime = TestRuntime::<LogApp>::new(vec![]);
+    runtime.run();
+
+    let (tx, rx) = oneshot::channel();
+    RECEIVER.with(|r| *r.borrow_mut() = Some(rx));
+    runtime.on_action(Action::Run);
+    runtime.run_until_stalled();
+    runtime.on_action(Action::Cancel);
+    let _ = tx.send("done".to_owned());
+    runtime.run_until_stalled();
+    assert!(runtime.state().is_empty());
+
+    let (tx, rx) = oneshot::channel();
+    RECEIVER.with(|r| *r.borrow_mut() = Some(rx));
+    runtime.on_action(Action::Run);
+    tx.send("done".to_owned()).unwrap();
+    runtime.run_until_stalled();
+    assert_eq!(runtime.state(), vec!["done"]);
+}
+
+#[test]
+fn route_unmapped_error() {
+    let runtime = TestRuntime::<LogApp>::new(vec![]);
+    runtime.set_error_handler(|e| Some(Action::Push(format!("error: {}", e))));
+    runtime.run();
+
+    let mut task = Task::empty();
+    task.try_push(future::ready(Err::<Action, _>("not found")));
+    task.append(Task::perform(
+        future::ready(Err::<String, _>(404)),
+        Action::Push,
+        |code| Action::Push(format!("status {}", code)),
+    ));
+    runtime.run_with_task(task);
+    runtime.run_until_stalled();
+    assert_eq!(runtime.state(), vec!["error: \"not found\"", "status 404"]);
+}
+
+#[test]
+fn call_after_render_without_state_change() {
+    let runtime = TestRuntime::<LogApp>::new(vec![]);
+    runtime.run();
+
+    runtime.on_action(Action::Focus);
+    assert_eq!(FOCUSED.with(|f| *f.borrow()), 1);
+
+    runtime.on_action(Action::Push("a".to_owned()));
+    assert_eq!(FOCUSED.with(|f| *f.borrow()), 1);
+}
+
+#[test]
+fn cancel_in_position_of_sequence() {
+    let runtime = TestRuntime::<LogApp>::new(vec![]);
+    runtime.run();
+
+    let (tx, rx) = oneshot::channel();
+    RECEIVER.with(|r| *r.borrow_mut() = Some(rx));
+    runtime.on_action(Action::Run);
+
+    let (second_tx, second_rx) = oneshot::channel();
+    let first = Task::perform(second_rx, Action::Push, |_| Action::Push("canceled".to_owned()));
+    runtime.run_with_task(Task::sequence(vec![first, Task::cancel("waiting")]));
+    runtime.run_until_stalled();
+    tx.send("done".to_owned()).unwrap();
+    runtime.run_until_stalled();
+    second_tx.send("second".to_owned()).unwrap();
+    runtime.run_until_stalled();
+    assert_eq!(runtime.state(), vec!["done", "second"]);
+}
+
+#[test]
+fn keep_running_task_on_empty_keyed_task() {
+    let runtime = TestRuntime::<LogApp>::new(vec![]);
+    runtime.run();
+
+    let (tx, rx) = oneshot::channel();
+    RECEIVER.with(|r| *r.borrow_mut() = Some(rx));
+    runtime.on_action(Action::Run);
+    runtime.run_with_task(Task::empty().with_key("waiting"));
+    tx.send("done".to_owned()).unwrap();
+    runtime.run_until_stalled();
+    assert_eq!(runtime.state(), vec!["done"]);
+}
+
+#[test]
+fn cancel_keyed_task_in_sequence() {
+    let runtime = TestRuntime::<LogApp>::new(vec![]);
+    runtime.run();
+
+    let (tx, rx) = oneshot::channel();
+    let waiting = Task::perform(rx, Action::Push, |_| Action::Push("canceled".to_owned()));
+    runtime.run_with_task(Task::sequence(vec![waiting.with_key("waiting"), push("next")]));
+    runtime.run_until_stalled();
+    runtime.on_action(Action::Cancel);
+    let _ = tx.send("done".to_owned());
+    runtime.run_until_stalled();
+    assert_eq!(runtime.state(), vec!["next"]);
+}
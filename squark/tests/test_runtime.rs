@@ -0,0 +1,194 @@
+use serde_json::json;
+use squark::{
+    handler, App, Child, HandlerArg, Ref, Runtime, Task, TestElement, TestNode, TestRuntime, View,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+struct State {
+    count: isize,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    Add(isize),
+    AddLater(isize),
+}
+
+#[derive(Clone, Default)]
+struct CounterApp;
+
+impl App for CounterApp {
+    type State = State;
+    type Action = Action;
+
+    fn reducer(&self, mut state: State, action: Action) -> (State, Task<Action>) {
+        let mut task = Task::empty();
+        match action {
+            Action::Add(n) => state.count += n,
+            Action::AddLater(n) => task.push(async move { Action::Add(n) }),
+        };
+        (state, task)
+    }
+
+    fn view(&self, state: State) -> View<Action> {
+        View::new(
+            "div".to_owned(),
+            vec![],
+            vec![],
+            vec![
+                Child::from(View::new(
+                    "span".to_owned(),
+                    vec![("class".to_owned(), "count".into())],
+                    vec![],
+                    vec![state.count.to_string().into()],
+                )),
+                Child::from(View::new(
+                    "button".to_owned(),
+                    vec![("id".to_owned(), "add".into())],
+                    vec![(
+                        "click".to_owned(),
+                        handler(|arg: HandlerArg| arg.as_i64().map(|n| Action::Add(n as isize))),
+                    )],
+                    vec!["add".into()],
+                )),
+                Child::from(View::new(
+                    "button".to_owned(),
+                    vec![("id".to_owned(), "add-later".into())],
+                    vec![("click".to_owned(), handler(|_| Some(Action::AddLater(10))))],
+                    vec!["add later".into()],
+                )),
+            ],
+        )
+    }
+}
+
+#[test]
+fn fire_event_and_render() {
+    let runtime = TestRuntime::<CounterApp>::new(State { count: 0 });
+    runtime.run();
+    assert_eq!(runtime.find("div span.count").unwrap().text(), "0");
+
+    runtime.fire("button#add", "click", json!(2));
+    runtime.fire("button#add", "click", json!(3));
+    assert_eq!(runtime.state(), State { count: 5 });
+    assert_eq!(runtime.find(".count").unwrap().text(), "5");
+    assert_eq!(runtime.find_all("button").len(), 2);
+}
+
+#[test]
+fn run_queued_task() {
+    let runtime = TestRuntime::<CounterApp>::new(State { count: 0 });
+    runtime.run();
+
+    runtime.fire("[id=add-later]", "click", json!(null));
+    assert_eq!(runtime.state(), State { count: 0 });
+
+    runtime.run_until_stalled();
+    assert_eq!(runtime.state(), State { count: 10 });
+    assert_eq!(runtime.find(".count").unwrap().text(), "10");
+}
+
+#[derive(Clone, Default)]
+struct RowsApp;
+
+impl App for RowsApp {
+    type State = State;
+    type Action = Action;
+
+    fn reducer(&self, mut state: State, action: Action) -> (State, Task<Action>) {
+        if let Action::Add(n) = action {
+            state.count += n;
+        }
+        (state, Task::empty())
+    }
+
+    fn view(&self, state: State) -> View<Action> {
+        let rows = (0..state.count).map(|i| {
+            View::fragment(vec![
+                View::new("dt".to_owned(), vec![], vec![], vec![i.to_string().into()]).into(),
+                View::new("dd".to_owned(), vec![], vec![], vec![]).into(),
+            ])
+        });
+        View::fragment(vec![
+            View::new(
+                "button".to_owned(),
+                vec![],
+                vec![("click".to_owned(), handler(|_| Some(Action::Add(-1))))],
+                vec![],
+            )
+            .into(),
+            rows.collect(),
+        ])
+    }
+}
+
+fn names(runtime: &TestRuntime<RowsApp>) -> Vec<String> {
+    runtime
+        .root()
+        .children()
+        .iter()
+        .map(|c| match c {
+            TestNode::Element(el) => el.name().to_owned(),
+            TestNode::Text(s) | TestNode::RawHtml(s) => s.to_owned(),
+        })
+        .collect()
+}
+
+#[test]
+fn render_fragment_into_root() {
+    let runtime = TestRuntime::<RowsApp>::new(State { count: 3 });
+    runtime.run();
+    assert_eq!(names(&runtime), vec!["button", "dt", "dd", "dt", "dd", "dt", "dd"]);
+
+    runtime.fire("button", "click", json!(null));
+    runtime.fire("button", "click", json!(null));
+    assert_eq!(names(&runtime), vec!["button", "dt", "dd"]);
+    assert_eq!(runtime.find("dt").unwrap().text(), "0");
+}
+
+thread_local! {
+    static INPUT: Ref = Ref::new();
+}
+
+/// Value of input, which is removed by `None`
+type Value = Option<String>;
+
+#[derive(Clone, Default)]
+struct RefApp;
+
+impl App for RefApp {
+    type State = Value;
+    type Action = Value;
+
+    fn reducer(&self, _: Value, value: Value) -> (Value, Task<Value>) {
+        (value, Task::empty())
+    }
+
+    fn view(&self, state: Value) -> View<Value> {
+        let input = state.map(|value| {
+            let attributes = vec![
+                ("ref".to_owned(), INPUT.with(Ref::to_owned).into()),
+                ("value".to_owned(), value.into()),
+            ];
+            View::new("input".to_owned(), attributes, vec![], vec![]).into()
+        });
+        View::new("div".to_owned(), vec![], vec![], input.into_iter().collect())
+    }
+}
+
+#[test]
+fn set_snapshot_of_element_to_ref() {
+    let runtime = TestRuntime::<RefApp>::new(Some("a".to_owned()));
+    runtime.run();
+    let value = || {
+        let el = INPUT.with(|r| r.get::<TestElement>());
+        el.and_then(|el| el.attribute("value").cloned())
+    };
+    assert_eq!(value(), Some("a".into()));
+
+    runtime.on_action(Some("b".to_owned()));
+    assert_eq!(value(), Some("b".into()));
+
+    runtime.on_action(None);
+    assert!(INPUT.with(|r| r.get::<TestElement>()).is_none());
+}